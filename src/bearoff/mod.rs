@@ -0,0 +1,163 @@
+//! Bearoff databases: exact numbers for positions where a side has every
+//! checker left in its home board.
+//!
+//! A side's home board is six counts, indexed by the combinatorial number
+//! system: the counts are read as a choice of 6 bars out of `checkers + 6`
+//! stars-and-bars slots. Indexes don't depend on how many checkers a database
+//! was built for, so a smaller database is a prefix of a bigger one.
+
+pub mod one_sided;
+pub mod two_sided;
+
+use crate::{
+    movegen::{simd::Simd1MoveGenerator, MoveGen},
+    types::{
+        board::{Board, BoardCoord},
+        dice::Dice,
+        prim::{BOrW, Bw},
+    },
+};
+
+/// points in a home board
+pub const POINTS: usize = 6;
+
+/// checkers a side has in total
+pub const MAX_CHECKERS: u8 = 15;
+
+/// `player`'s checkers on their points 1 to 6, `None` if some of them are
+/// still anywhere else. Borne-off checkers are simply not on the board.
+pub fn home_counts(board: &Board, player: Bw) -> Option<[u8; POINTS]> {
+    let outside = (7..=25)
+        .map(|i| board[BoardCoord::rel(player, i)])
+        .any(|v| v.matches(player));
+
+    if outside {
+        return None;
+    }
+
+    let mut counts = [0; POINTS];
+    for (i, count) in counts.iter_mut().enumerate() {
+        let v = board[BoardCoord::rel(player, i as u8 + 1)];
+        if v.matches(player) {
+            *count = v.to_count();
+        }
+    }
+
+    Some(counts)
+}
+
+/// a board with only `player`'s home checkers on it
+pub fn home_board(counts: &[u8; POINTS], player: Bw) -> Board {
+    counts
+        .iter()
+        .zip(1..)
+        .filter(|(n, _)| **n > 0)
+        .map(|(n, i)| (BoardCoord::rel(player, i), BOrW::from((player, *n))))
+        .collect()
+}
+
+/// Indexes of the home boards `dice` can play `counts` to. With everything
+/// home some die can always be played, so there's at least one unless
+/// everything is off already.
+pub fn moves(counts: &[u8; POINTS], dice: Dice) -> Vec<usize> {
+    if counts.iter().all(|n| *n == 0) {
+        return vec![];
+    }
+
+    Simd1MoveGenerator::gen_unique_moves(&home_board(counts, Bw::White), dice, Bw::White)
+        .iter()
+        .map(|m| index(&home_counts(m, Bw::White).expect("left the home board")))
+        .collect()
+}
+
+/// how many arrangements there are of up to `checkers` checkers
+pub fn positions(checkers: u8) -> usize {
+    binomial(checkers as usize + POINTS, POINTS)
+}
+
+/// The index of a home board among all arrangements with as many checkers or
+/// fewer.
+pub fn index(counts: &[u8; POINTS]) -> usize {
+    let mut bar = 0;
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(k, n)| {
+            bar += *n as usize + (k > 0) as usize;
+            binomial(bar, k + 1)
+        })
+        .sum()
+}
+
+/// every arrangement of up to `checkers` checkers, fewest pips first so that
+/// all positions a move leads to come before it
+pub fn arrangements(checkers: u8) -> Vec<[u8; POINTS]> {
+    fn fill(counts: &mut [u8; POINTS], point: usize, left: u8, out: &mut Vec<[u8; POINTS]>) {
+        if point == POINTS {
+            out.push(*counts);
+            return;
+        }
+
+        for n in 0..=left {
+            counts[point] = n;
+            fill(counts, point + 1, left - n, out);
+        }
+        counts[point] = 0;
+    }
+
+    let mut out = Vec::with_capacity(positions(checkers));
+    fill(&mut [0; POINTS], 0, checkers, &mut out);
+    out.sort_by_key(pips);
+
+    out
+}
+
+pub fn pips(counts: &[u8; POINTS]) -> u32 {
+    counts.iter().zip(1..).map(|(n, i)| *n as u32 * i).sum()
+}
+
+pub(crate) fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::types::{board::Board, prim::Bw};
+
+    use super::{arrangements, home_board, home_counts, index, positions};
+
+    #[test]
+    fn test_index() {
+        assert_eq!(positions(15), 54264);
+        assert_eq!(index(&[0; 6]), 0);
+
+        let all = arrangements(15);
+        assert_eq!(all.len(), positions(15));
+
+        let indexes: HashSet<_> = all.iter().map(index).collect();
+        assert_eq!(indexes.len(), all.len());
+        assert!(indexes.iter().all(|i| *i < all.len()));
+
+        // smaller databases are a prefix
+        assert!(arrangements(3).iter().all(|c| index(c) < positions(3)));
+    }
+
+    #[test]
+    fn test_home_counts() {
+        assert_eq!(home_counts(&Board::default(), Bw::White), None);
+
+        let counts = [1, 0, 3, 0, 0, 2];
+        for player in [Bw::White, Bw::Black] {
+            let board = home_board(&counts, player);
+            assert_eq!(home_counts(&board, player), Some(counts));
+            assert_eq!(home_counts(&board, -player), Some([0; 6]));
+        }
+    }
+}
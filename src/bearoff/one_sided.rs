@@ -0,0 +1,229 @@
+//! The one-sided database: for every home board of up to 15 checkers, the
+//! chance of needing exactly `n` rolls to bear them all off, playing to need
+//! as few rolls as possible on average.
+//!
+//! The file is little-endian and has no pointers or padding, so it can be
+//! memory-mapped and handed to [`BearoffDb::from_bytes`] as it is:
+//!
+//! | bytes     | contents                                              |
+//! |-----------|-------------------------------------------------------|
+//! | 0..8      | [`MAGIC`]                                             |
+//! | 8..12     | u32, the most checkers in a position                  |
+//! | 12..16    | u32, [`ROLLS`]                                        |
+//! | 16..      | `ROLLS` u16 per position in [`index`] order, `p * 65535` |
+
+use std::{fs, path::Path};
+
+use anyhow::ensure;
+
+use crate::{
+    types::{board::Board, dice::Dice, prim::Bw},
+    util::table::TableFormat,
+};
+
+use super::{arrangements, home_counts, index, moves, positions, POINTS};
+
+pub const MAGIC: &[u8; 8] = b"BGBEAR1\0";
+
+/// Rolls a distribution goes up to. 15 checkers on the 6 point rolling 2-1
+/// every time take 30.
+pub const ROLLS: usize = 32;
+
+const FORMAT: TableFormat<2> = TableFormat {
+    magic: MAGIC,
+    name: "bearoff database",
+};
+
+const HEADER: usize = FORMAT.header_len();
+
+const SCALE: f64 = u16::MAX as f64;
+
+/// A one-sided bearoff database, over anything that holds the bytes of its
+/// file: a `Vec<u8>`, a `&[u8]`, a memory map...
+pub struct BearoffDb<B = Vec<u8>> {
+    data: B,
+    checkers: u8,
+}
+
+impl BearoffDb {
+    /// Works out every position of up to `checkers` checkers, from the fewest
+    /// pips up. 15 checkers take a while, build those with `--release`.
+    pub fn generate(checkers: u8) -> Self {
+        assert!(checkers <= super::MAX_CHECKERS, "at most 15 checkers");
+
+        let mut distributions = vec![[0f64; ROLLS]; positions(checkers)];
+        let mut expected = vec![0f64; positions(checkers)];
+        distributions[0][0] = 1.0;
+
+        for counts in arrangements(checkers).into_iter().skip(1) {
+            let mut distribution = [0f64; ROLLS];
+
+            for (dice, n) in Dice::iter_unique() {
+                let best = moves(&counts, dice)
+                    .into_iter()
+                    .min_by(|a, b| expected[*a].total_cmp(&expected[*b]))
+                    .expect("no moves in a bearoff");
+
+                let next = &distributions[best];
+                assert_eq!(next[ROLLS - 1], 0.0, "takes more than {ROLLS} rolls");
+
+                for rolls in 1..ROLLS {
+                    distribution[rolls] += next[rolls - 1] * n as f64 / 36.0;
+                }
+            }
+
+            let i = index(&counts);
+            expected[i] = distribution
+                .iter()
+                .zip(0..)
+                .map(|(p, n)| p * n as f64)
+                .sum();
+            distributions[i] = distribution;
+        }
+
+        let mut data = FORMAT.start(
+            [checkers as u32, ROLLS as u32],
+            distributions.len() * ROLLS * 2,
+        );
+
+        for p in distributions.iter().flatten() {
+            data.extend_from_slice(&((p * SCALE).round() as u16).to_le_bytes());
+        }
+
+        Self { data, checkers }
+    }
+
+    /// reads a whole database file into memory
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+}
+
+impl<B: AsRef<[u8]>> BearoffDb<B> {
+    pub fn from_bytes(data: B) -> anyhow::Result<Self> {
+        let [checkers, _] = FORMAT.read(data.as_ref(), |[checkers, rolls]| {
+            ensure!(
+                checkers <= super::MAX_CHECKERS as u32 && rolls as usize == ROLLS,
+                "unsupported bearoff database: {checkers} checkers, {rolls} rolls"
+            );
+
+            Ok(positions(checkers as u8) * ROLLS * 2)
+        })?;
+
+        Ok(Self {
+            data,
+            checkers: checkers as u8,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        Ok(fs::write(path, self.as_bytes())?)
+    }
+
+    /// the most checkers a position in here can have
+    pub fn checkers(&self) -> u8 {
+        self.checkers
+    }
+
+    /// Chances that `player` needs exactly `n` more rolls to bear off, `None`
+    /// when they aren't bearing off or have too many checkers left for this
+    /// database.
+    pub fn distribution(&self, board: &Board, player: Bw) -> Option<[f32; ROLLS]> {
+        let counts = home_counts(board, player)?;
+        self.distribution_of(&counts)
+    }
+
+    /// the average number of rolls `player` needs to bear off
+    pub fn expected_rolls(&self, board: &Board, player: Bw) -> Option<f32> {
+        let distribution = self.distribution(board, player)?;
+
+        Some(
+            distribution
+                .iter()
+                .zip(0..)
+                .map(|(p, n)| p * n as f32)
+                .sum(),
+        )
+    }
+
+    /// [`distribution`](Self::distribution) straight from the counts on the
+    /// points 1 to 6
+    pub fn distribution_of(&self, counts: &[u8; POINTS]) -> Option<[f32; ROLLS]> {
+        if counts.iter().map(|n| *n as u32).sum::<u32>() > self.checkers as u32 {
+            return None;
+        }
+
+        let start = HEADER + index(counts) * ROLLS * 2;
+        let record = &self.as_bytes()[start..start + ROLLS * 2];
+
+        let mut distribution = [0.0; ROLLS];
+        for (p, bytes) in distribution.iter_mut().zip(record.chunks_exact(2)) {
+            *p = (u16::from_le_bytes([bytes[0], bytes[1]]) as f64 / SCALE) as f32;
+        }
+
+        Some(distribution)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        bearoff::home_board,
+        types::{board::Board, prim::Bw},
+    };
+
+    use super::BearoffDb;
+
+    #[test]
+    fn test_small_db() {
+        let db = BearoffDb::generate(3);
+
+        assert_eq!(db.expected_rolls(&Board::default(), Bw::White), None);
+        assert_eq!(db.expected_rolls(&Board::empty(), Bw::White), Some(0.0));
+
+        assert_eq!(db.distribution_of(&[1, 0, 0, 0, 0, 0]).unwrap()[1], 1.0);
+
+        // a checker on the 6 point stays on with 1-1, 2-1, 3-1, 4-1 and 3-2,
+        // 9 rolls of 36
+        let single = db.distribution_of(&[0, 0, 0, 0, 0, 1]).unwrap();
+        assert!((single[1] - 27.0 / 36.0).abs() < 1e-4);
+
+        // two on the 6 point need 3-3 or better doubles to go at once
+        let board = home_board(&[0, 0, 0, 0, 0, 2], Bw::Black);
+        let two = db.distribution(&board, Bw::Black).unwrap();
+        assert!((two[1] - 4.0 / 36.0).abs() < 1e-4);
+        assert!((two.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        assert_eq!(
+            db.expected_rolls(&board, Bw::Black),
+            db.expected_rolls(&board.inverse(), Bw::White)
+        );
+
+        assert_eq!(db.distribution_of(&[1, 1, 1, 1, 0, 0]), None);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let db = BearoffDb::generate(2);
+        let path = std::env::temp_dir().join("backgammon-simd-test-bearoff.db");
+        db.write(&path).unwrap();
+
+        let read = BearoffDb::open(&path).unwrap();
+        assert_eq!(read.checkers(), 2);
+        assert_eq!(read.as_bytes(), db.as_bytes());
+
+        let borrowed = BearoffDb::from_bytes(db.as_bytes()).unwrap();
+        assert_eq!(
+            borrowed.distribution_of(&[2, 0, 0, 0, 0, 0]),
+            db.distribution_of(&[2, 0, 0, 0, 0, 0])
+        );
+
+        assert!(BearoffDb::from_bytes(&db.as_bytes()[..40]).is_err());
+        assert!(BearoffDb::from_bytes(&b"not a database"[..]).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
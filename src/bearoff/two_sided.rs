@@ -0,0 +1,280 @@
+//! The two-sided database: the exact cubeless chance of winning for the side
+//! on roll, when both sides are bearing off with up to some number of
+//! checkers each.
+//!
+//! Every side's moves are generated once, then positions are worked out from
+//! the fewest pips in total up, so every position a roll leads to is known.
+//! The table is `positions²` big: 6 checkers a side is 1.7MB, 8 is 18MB,
+//! which is as far as it goes, see [`MAX_TWO_SIDED_CHECKERS`].
+//!
+//! Like the one-sided file it's little-endian and fit to be memory-mapped:
+//! [`MAGIC`], the checkers per side as a u32, then a u16 `p * 65535` for every
+//! pair of [`index`]es, the side on roll's first.
+
+use std::{fs, path::Path};
+
+use anyhow::ensure;
+
+use crate::{
+    eval::{Evaluator, Probabilities},
+    types::{board::Board, dice::Dice, prim::Bw},
+    util::table::TableFormat,
+};
+
+use super::{arrangements, home_counts, index, moves, pips, positions, MAX_CHECKERS, POINTS};
+
+pub const MAGIC: &[u8; 8] = b"BGBEAR2\0";
+
+const FORMAT: TableFormat<1> = TableFormat {
+    magic: MAGIC,
+    name: "two-sided bearoff database",
+};
+
+const HEADER: usize = FORMAT.header_len();
+
+const SCALE: f32 = u16::MAX as f32;
+
+/// The most checkers a side can have in a two-sided database. The table and
+/// the moves kept while building it grow with `positions²`, which for all 15
+/// checkers is billions of entries.
+pub const MAX_TWO_SIDED_CHECKERS: u8 = 8;
+
+pub struct TwoSidedBearoff<B = Vec<u8>> {
+    data: B,
+    checkers: u8,
+}
+
+impl TwoSidedBearoff {
+    pub fn generate(checkers: u8) -> anyhow::Result<Self> {
+        ensure!(
+            checkers <= MAX_TWO_SIDED_CHECKERS,
+            "two-sided bearoff databases go up to {MAX_TWO_SIDED_CHECKERS} checkers, not {checkers}"
+        );
+
+        let n = positions(checkers);
+        let rolls = Dice::iter_unique().collect::<Vec<_>>();
+
+        let mut children = vec![Vec::new(); n];
+        let mut by_pips = vec![Vec::new(); checkers as usize * POINTS + 1];
+
+        for counts in arrangements(checkers) {
+            let i = index(&counts);
+            children[i] = rolls
+                .iter()
+                .map(|(dice, _)| moves(&counts, *dice))
+                .collect();
+            by_pips[pips(&counts) as usize].push(i);
+        }
+
+        // `win[on_roll * n + opponent]`: on roll with nothing left has won
+        // already, and with the opponent gone they've lost, so bearing off
+        // the last checker is worth `1 - 0`
+        let mut win = vec![0f32; n * n];
+        win[..n].fill(1.0);
+
+        let most = by_pips.len() - 1;
+        for total in 2..=2 * most {
+            for own in total.saturating_sub(most).max(1)..=most.min(total - 1) {
+                for &a in &by_pips[own] {
+                    for &b in &by_pips[total - own] {
+                        win[a * n + b] = rolls
+                            .iter()
+                            .zip(&children[a])
+                            .map(|((_, weight), next)| {
+                                let best = next
+                                    .iter()
+                                    .map(|c| 1.0 - win[b * n + c])
+                                    .fold(0.0, f32::max);
+
+                                best * *weight as f32 / 36.0
+                            })
+                            .sum();
+                    }
+                }
+            }
+        }
+
+        let mut data = FORMAT.start([checkers as u32], n * n * 2);
+
+        for p in win {
+            data.extend_from_slice(&((p * SCALE).round() as u16).to_le_bytes());
+        }
+
+        Ok(Self { data, checkers })
+    }
+
+    /// reads a whole database file into memory
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+}
+
+impl<B: AsRef<[u8]>> TwoSidedBearoff<B> {
+    pub fn from_bytes(data: B) -> anyhow::Result<Self> {
+        let [checkers] = FORMAT.read(data.as_ref(), |[checkers]| {
+            ensure!(
+                checkers <= MAX_TWO_SIDED_CHECKERS as u32,
+                "unsupported bearoff database: {checkers} checkers"
+            );
+
+            let n = positions(checkers as u8);
+            Ok(n * n * 2)
+        })?;
+
+        Ok(Self {
+            data,
+            checkers: checkers as u8,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        Ok(fs::write(path, self.as_bytes())?)
+    }
+
+    /// the most checkers a side can have in here
+    pub fn checkers(&self) -> u8 {
+        self.checkers
+    }
+
+    /// Cubeless chance that `player`, who's on roll, wins. `None` unless both
+    /// sides are bearing off with few enough checkers for this database.
+    pub fn win_prob(&self, board: &Board, player: Bw) -> Option<f32> {
+        self.win_prob_of(&home_counts(board, player)?, &home_counts(board, -player)?)
+    }
+
+    /// [`win_prob`](Self::win_prob) straight from the counts on the points 1
+    /// to 6, the side on roll's first
+    pub fn win_prob_of(&self, own: &[u8; POINTS], opponent: &[u8; POINTS]) -> Option<f32> {
+        let fits = |counts: &[u8; POINTS]| counts.iter().sum::<u8>() <= self.checkers;
+        if !fits(own) || !fits(opponent) {
+            return None;
+        }
+
+        let start = HEADER + (index(own) * positions(self.checkers) + index(opponent)) * 2;
+        let bytes = &self.as_bytes()[start..start + 2];
+
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / SCALE)
+    }
+}
+
+/// Looks bearoffs up in `db` and asks `fallback` about everything else.
+///
+/// While a side still has all 15 checkers it can be gammoned, which the
+/// database doesn't know about, so those go to `fallback` too.
+pub struct BearoffEvaluator<E, B = Vec<u8>> {
+    pub db: TwoSidedBearoff<B>,
+    pub fallback: E,
+}
+
+impl<E: Evaluator, B: AsRef<[u8]>> Evaluator for BearoffEvaluator<E, B> {
+    fn evaluate(&self, board: &Board, player: Bw) -> Probabilities {
+        let counts = home_counts(board, player).zip(home_counts(board, -player));
+
+        if let Some((own, opponent)) = counts {
+            let started = |counts: &[u8; POINTS]| counts.iter().sum::<u8>() < MAX_CHECKERS;
+
+            if started(&own) && started(&opponent) {
+                if let Some(win) = self.db.win_prob_of(&own, &opponent) {
+                    return Probabilities {
+                        win,
+                        ..Default::default()
+                    };
+                }
+            }
+        }
+
+        self.fallback.evaluate(board, player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        eval::{Evaluator, PipCountEvaluator, Probabilities},
+        types::{
+            board::{Board, BoardCoord},
+            prim::{BOrW, Bw},
+        },
+    };
+
+    use super::{BearoffEvaluator, TwoSidedBearoff};
+
+    #[test]
+    fn test_win_prob() {
+        let db = TwoSidedBearoff::generate(3).unwrap();
+
+        assert_eq!(db.win_prob(&Board::default(), Bw::White), None);
+        assert_eq!(
+            db.win_prob_of(&[1, 0, 0, 0, 0, 0], &[0, 0, 0, 0, 0, 3]),
+            Some(1.0)
+        );
+
+        // Black needs 3-3 or better doubles to get two off the 6 point before
+        // White's last checker goes
+        let board: Board = [
+            (BoardCoord(1), BOrW::white(1)),
+            (BoardCoord(19), BOrW::black(2)),
+        ]
+        .into_iter()
+        .collect();
+        let black = db.win_prob(&board, Bw::Black).unwrap();
+        assert!((black - 4.0 / 36.0).abs() < 1e-4);
+        assert_eq!(db.win_prob(&board.inverse(), Bw::White), Some(black));
+
+        // a checker each on the 6 point: whoever misses is certain to get off
+        // with the next roll
+        let win = db
+            .win_prob_of(&[0, 0, 0, 0, 0, 1], &[0, 0, 0, 0, 0, 1])
+            .unwrap();
+        assert!((win - (27.0 + 9.0 * 9.0 / 36.0) / 36.0).abs() < 1e-4);
+
+        assert_eq!(
+            db.win_prob_of(&[4, 0, 0, 0, 0, 0], &[1, 0, 0, 0, 0, 0]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bytes() {
+        let db = TwoSidedBearoff::generate(2).unwrap();
+        assert!(TwoSidedBearoff::generate(15).is_err());
+        let read = TwoSidedBearoff::from_bytes(db.as_bytes()).unwrap();
+
+        assert_eq!(read.checkers(), 2);
+        assert_eq!(
+            read.win_prob_of(&[0, 1, 0, 0, 0, 1], &[0, 0, 2, 0, 0, 0]),
+            db.win_prob_of(&[0, 1, 0, 0, 0, 1], &[0, 0, 2, 0, 0, 0])
+        );
+        assert!(TwoSidedBearoff::from_bytes(&db.as_bytes()[..20]).is_err());
+    }
+
+    #[test]
+    fn test_evaluator() {
+        let evaluator = BearoffEvaluator {
+            db: TwoSidedBearoff::generate(2).unwrap(),
+            fallback: PipCountEvaluator,
+        };
+
+        let board: Board = [
+            (BoardCoord(2), BOrW::white(1)),
+            (BoardCoord(20), BOrW::black(2)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            evaluator.evaluate(&board, Bw::White),
+            Probabilities {
+                win: 1.0,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            evaluator.evaluate(&Board::default(), Bw::White),
+            PipCountEvaluator.evaluate(&Board::default(), Bw::White)
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Builds the bearoff databases:
+//!
+//! ```sh
+//! cargo run --release --bin bearoff -- bearoff.db [checkers]
+//! cargo run --release --bin bearoff -- --two-sided bearoff2.db [checkers]
+//! ```
+//!
+//! The one-sided one defaults to all 15 checkers, the two-sided one to 6 a
+//! side, out of at most 8.
+
+use std::{env, time::Instant};
+
+use anyhow::Context;
+use backgammon_simd::bearoff::{
+    one_sided::BearoffDb,
+    positions,
+    two_sided::{TwoSidedBearoff, MAX_TWO_SIDED_CHECKERS},
+    MAX_CHECKERS,
+};
+
+const USAGE: &str = "usage: bearoff [--two-sided] <output file> [checkers]";
+
+fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1).peekable();
+
+    let two_sided = args.next_if(|arg| arg == "--two-sided").is_some();
+    let path = args.next().context(USAGE)?;
+    let checkers = match args.next() {
+        Some(n) => n.parse().context("checkers should be a number")?,
+        None if two_sided => 6,
+        None => MAX_CHECKERS,
+    };
+
+    let max = if two_sided {
+        MAX_TWO_SIDED_CHECKERS
+    } else {
+        MAX_CHECKERS
+    };
+    anyhow::ensure!(checkers <= max, "at most {max} checkers");
+
+    let started = Instant::now();
+    if two_sided {
+        TwoSidedBearoff::generate(checkers)?.write(&path)?;
+    } else {
+        BearoffDb::generate(checkers).write(&path)?;
+    }
+
+    println!(
+        "{} positions of up to {checkers} checkers in {:.1?}, written to {path}",
+        positions(checkers),
+        started.elapsed()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+//! Solves 3-checker hypergammon:
+//!
+//! ```sh
+//! cargo run --release --bin hypergammon -- hypergammon.db [tolerance]
+//! ```
+
+use std::{env, time::Instant};
+
+use anyhow::Context;
+use backgammon_simd::hypergammon::{opening, Hypergammon, SolverSettings};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+
+    let path = args
+        .next()
+        .context("usage: hypergammon <output file> [tolerance]")?;
+    let mut settings = SolverSettings::default();
+    if let Some(tolerance) = args.next() {
+        settings.tolerance = tolerance.parse().context("tolerance should be a number")?;
+    }
+
+    let started = Instant::now();
+    Hypergammon::solve(&opening(), settings).write(&path)?;
+
+    println!("solved in {:.1?}, written to {path}", started.elapsed());
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::{
+    eval::Evaluator,
+    types::{board::Board, prim::Bw},
+};
+
+use super::MoveDecision;
+
+/// Picks the move with the best cubeless equity one ply ahead.
+pub struct GreedyDecision<E>(pub E);
+
+impl<E: Evaluator> MoveDecision for GreedyDecision<E> {
+    fn choose(&mut self, player: Bw, moves: Vec<Board>) -> Board {
+        moves
+            .into_iter()
+            .map(|board| {
+                let equity = self.0.evaluate_after(&board, player).equity();
+                (board, equity)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("no moves to choose from")
+            .0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        decision::{MoveDecision, RandomMoveDecision},
+        eval::PipCountEvaluator,
+        game::Game,
+        movegen::{simd::Simd1MoveGenerator, MoveGen},
+        types::{
+            board::{Board, BoardCoord},
+            dice::{Dice, Die},
+            prim::{BOrW, Bw},
+        },
+    };
+
+    use super::GreedyDecision;
+
+    #[test]
+    fn test_bears_off() {
+        let board: Board = [
+            (BoardCoord(1), BOrW::white(1)),
+            (BoardCoord(5), BOrW::white(1)),
+            (BoardCoord(20), BOrW::black(15)),
+        ]
+        .into_iter()
+        .collect();
+
+        let moves = Simd1MoveGenerator::gen_unique_moves(&board, Dice(Die(5), Die(1)), Bw::White);
+        let chosen = GreedyDecision(PipCountEvaluator).choose(Bw::White, moves);
+
+        assert_eq!(chosen.winner(), Some(Bw::White));
+    }
+
+    #[test]
+    fn test_beats_random() {
+        let mut wins = 0;
+
+        for seed in 0..100 {
+            // the dice, then the random side
+            let rng = |stream: u64| StdRng::seed_from_u64(seed * 3 + stream);
+            let mut game = Game::new(&mut rng(0));
+
+            game.play(
+                rng(1),
+                &mut GreedyDecision(PipCountEvaluator),
+                &mut RandomMoveDecision(rng(2)),
+            )
+            .for_each(drop);
+
+            if game.result().unwrap().winner == Bw::White {
+                wins += 1;
+            }
+        }
+
+        assert!(wins > 70, "{} wins out of 100", wins);
+    }
+}
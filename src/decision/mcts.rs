@@ -0,0 +1,475 @@
+//! Monte Carlo Tree Search with chance nodes.
+//!
+//! A decision node is a board, the player on roll and their dice; its children
+//! are the afterstates of the legal moves. An afterstate is a chance node,
+//! whose children are the opponent's decision nodes for each of the 21 rolls.
+//! Rolls are sampled by their probability, moves picked by UCT or PUCT.
+
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    eval::{Evaluator, Probabilities},
+    game::{Game, GameState},
+    movegen::{simd::Simd1MoveGenerator, MoveGen},
+    types::{
+        board::Board,
+        dice::{Dice, Die},
+        prim::Bw,
+    },
+};
+
+use super::{MoveDecision, RandomMoveDecision};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    /// UCB1: `Q + c * sqrt(ln N / n)`
+    Uct { c: f32 },
+    /// AlphaZero's: `Q + c * P * sqrt(N) / (1 + n)`, with the priors `P` from
+    /// the evaluator
+    Puct { c: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsSettings {
+    pub budget: Budget,
+    pub selection: Selection,
+    /// Priors are a softmax of the evaluator's equities divided by this.
+    pub temperature: f32,
+    /// Value new leaves by playing random moves to the end instead of asking
+    /// the evaluator. A policy set with `Mcts::with_policy` takes precedence.
+    pub random_playouts: bool,
+}
+
+impl Default for MctsSettings {
+    fn default() -> Self {
+        Self {
+            budget: Budget::Iterations(1000),
+            selection: Selection::Puct { c: 1.5 },
+            temperature: 0.25,
+            random_playouts: false,
+        }
+    }
+}
+
+/// a board after `player` has moved
+struct Chance {
+    board: Board,
+    player: Bw,
+    prior: f32,
+    visits: u32,
+    /// total equity for `player`
+    value: f64,
+    /// decision nodes by roll, in `Dice::iter_unique` order
+    rolls: [Option<usize>; 21],
+}
+
+struct Decision {
+    board: Board,
+    player: Bw,
+    dice: Dice,
+    visits: u32,
+    /// `None` until expanded
+    children: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveStats {
+    pub board: Board,
+    pub visits: u32,
+    /// mean equity of the mover
+    pub equity: f32,
+}
+
+pub struct Mcts<E, R, P = RandomMoveDecision<StdRng>, Generator: MoveGen = Simd1MoveGenerator> {
+    pub evaluator: E,
+    pub settings: MctsSettings,
+    rng: R,
+    policy: Option<P>,
+    chances: Vec<Chance>,
+    decisions: Vec<Decision>,
+    generator: PhantomData<Generator>,
+}
+
+impl<E: Evaluator, R: Rng> Mcts<E, R> {
+    pub fn new(evaluator: E, settings: MctsSettings, rng: R) -> Self {
+        Self {
+            evaluator,
+            settings,
+            rng,
+            policy: None,
+            chances: vec![],
+            decisions: vec![],
+            generator: PhantomData,
+        }
+    }
+}
+
+impl<E: Evaluator, R: Rng, P: MoveDecision, Generator: MoveGen> Mcts<E, R, P, Generator> {
+    /// value new leaves by playing them out with `policy`
+    pub fn with_policy<Q: MoveDecision>(self, policy: Q) -> Mcts<E, R, Q, Generator> {
+        Mcts {
+            evaluator: self.evaluator,
+            settings: self.settings,
+            rng: self.rng,
+            policy: Some(policy),
+            chances: vec![],
+            decisions: vec![],
+            generator: PhantomData,
+        }
+    }
+
+    /// Searches `player`'s candidate moves and reports on each.
+    pub fn analyze(&mut self, player: Bw, moves: Vec<Board>) -> Vec<MoveStats> {
+        self.chances.clear();
+        self.decisions.clear();
+
+        // the root's moves are given, so it never needs its board or dice
+        let children = self.add_chances(moves, player);
+        self.decisions.push(Decision {
+            board: Board::empty(),
+            player,
+            dice: Dice(Die(0), Die(0)),
+            visits: 0,
+            children: Some(children),
+        });
+
+        let start = Instant::now();
+        let mut iterations = 0;
+
+        loop {
+            match self.settings.budget {
+                Budget::Iterations(n) if iterations >= n => break,
+                Budget::Time(t) if iterations > 0 && start.elapsed() >= t => break,
+                _ => {}
+            }
+
+            self.iterate();
+            iterations += 1;
+        }
+
+        self.decisions[0]
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|&c| {
+                let chance = &self.chances[c];
+
+                MoveStats {
+                    board: chance.board.clone(),
+                    visits: chance.visits,
+                    equity: (chance.value / chance.visits.max(1) as f64) as f32,
+                }
+            })
+            .collect()
+    }
+
+    fn add_chances(&mut self, moves: Vec<Board>, player: Bw) -> Vec<usize> {
+        let priors = match self.settings.selection {
+            Selection::Uct { .. } => vec![1.0 / moves.len() as f32; moves.len()],
+            Selection::Puct { .. } => {
+                let equities = moves
+                    .iter()
+                    .map(|b| self.evaluator.evaluate_after(b, player).equity())
+                    .collect::<Vec<_>>();
+                let best = equities.iter().copied().fold(f32::MIN, f32::max);
+
+                let weights = equities
+                    .iter()
+                    .map(|e| ((e - best) / self.settings.temperature).exp())
+                    .collect::<Vec<_>>();
+                let total: f32 = weights.iter().sum();
+
+                weights.into_iter().map(|w| w / total).collect()
+            }
+        };
+
+        moves
+            .into_iter()
+            .zip(priors)
+            .map(|(board, prior)| {
+                self.chances.push(Chance {
+                    board,
+                    player,
+                    prior,
+                    visits: 0,
+                    value: 0.0,
+                    rolls: [None; 21],
+                });
+
+                self.chances.len() - 1
+            })
+            .collect()
+    }
+
+    fn select(&self, decision: usize) -> usize {
+        let parent = &self.decisions[decision];
+        let children = parent.children.as_ref().unwrap();
+        let n = parent.visits.max(1) as f32;
+
+        let score = |c: usize| {
+            let chance = &self.chances[c];
+            let q = (chance.value / chance.visits.max(1) as f64) as f32;
+
+            match self.settings.selection {
+                Selection::Uct { .. } if chance.visits == 0 => f32::INFINITY,
+                Selection::Uct { c } => q + c * (n.ln() / chance.visits as f32).sqrt(),
+                Selection::Puct { c } => {
+                    q + c * chance.prior * n.sqrt() / (1.0 + chance.visits as f32)
+                }
+            }
+        };
+
+        *children
+            .iter()
+            .max_by(|a, b| score(**a).total_cmp(&score(**b)))
+            .unwrap()
+    }
+
+    fn iterate(&mut self) {
+        let mut decision = 0;
+        let mut path = vec![];
+
+        let value = loop {
+            self.decisions[decision].visits += 1;
+
+            if self.decisions[decision].children.is_none() {
+                let Decision {
+                    board,
+                    player,
+                    dice,
+                    ..
+                } = &self.decisions[decision];
+                let (board, player) = (board.clone(), *player);
+
+                let mut moves = Generator::gen_unique_moves(&board, *dice, player);
+                if moves.is_empty() {
+                    moves.push(board);
+                }
+
+                let children = self.add_chances(moves, player);
+                self.decisions[decision].children = Some(children);
+            }
+
+            let chance = self.select(decision);
+            path.push(chance);
+
+            let Chance {
+                board,
+                player,
+                visits,
+                ..
+            } = &self.chances[chance];
+
+            if let Some(p) = Probabilities::finished(board, *player) {
+                break (*player, p.equity());
+            }
+
+            if *visits == 0 {
+                let (board, player) = (board.clone(), *player);
+                break (player, self.leaf_value(&board, player));
+            }
+
+            let roll = self.sample_roll();
+            decision = match self.chances[chance].rolls[roll] {
+                Some(decision) => decision,
+                None => {
+                    let node = &self.chances[chance];
+                    self.decisions.push(Decision {
+                        board: node.board.clone(),
+                        player: -node.player,
+                        dice: Dice::iter_unique().nth(roll).unwrap().0,
+                        visits: 0,
+                        children: None,
+                    });
+
+                    let new = self.decisions.len() - 1;
+                    self.chances[chance].rolls[roll] = Some(new);
+                    new
+                }
+            };
+        };
+
+        let (owner, equity) = value;
+        for c in path {
+            let chance = &mut self.chances[c];
+            chance.visits += 1;
+            chance.value += if chance.player == owner {
+                equity as f64
+            } else {
+                -equity as f64
+            };
+        }
+    }
+
+    /// index into `Dice::iter_unique`
+    fn sample_roll(&mut self) -> usize {
+        let mut n = self.rng.gen_range(0..36);
+
+        for (i, (_, weight)) in Dice::iter_unique().enumerate() {
+            if n < weight {
+                return i;
+            }
+            n -= weight;
+        }
+
+        unreachable!()
+    }
+
+    /// equity of `player`, who just moved to `board`
+    fn leaf_value(&mut self, board: &Board, player: Bw) -> f32 {
+        if self.policy.is_none() && !self.settings.random_playouts {
+            return self.evaluator.evaluate_after(board, player).equity();
+        }
+
+        let mut game = Game {
+            board: board.clone(),
+            state: GameState::Dice(-player, self.rng.gen()),
+            cube: None,
+            match_score: None,
+        };
+
+        match &mut self.policy {
+            Some(policy) => game.play_self(&mut self.rng, policy).for_each(drop),
+            None => {
+                let mut random = RandomMoveDecision(StdRng::seed_from_u64(self.rng.gen()));
+                game.play_self(&mut self.rng, &mut random).for_each(drop)
+            }
+        }
+
+        Probabilities::finished(&game.board, player)
+            .expect("playout didn't finish")
+            .equity()
+    }
+}
+
+impl<E: Evaluator, R: Rng, P: MoveDecision, Generator: MoveGen> MoveDecision
+    for Mcts<E, R, P, Generator>
+{
+    /// the most visited move
+    fn choose(&mut self, player: Bw, moves: Vec<Board>) -> Board {
+        self.analyze(player, moves)
+            .into_iter()
+            .max_by_key(|stats| stats.visits)
+            .expect("no moves to choose from")
+            .board
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        decision::{greedy::GreedyDecision, MoveDecision, RandomMoveDecision},
+        eval::PipCountEvaluator,
+        game::Game,
+        movegen::{simd::Simd1MoveGenerator, MoveGen},
+        types::{
+            board::{Board, BoardCoord},
+            dice::{Dice, Die},
+            prim::{BOrW, Bw},
+        },
+    };
+
+    use super::{Budget, Mcts, MctsSettings, Selection};
+
+    fn bearoff() -> Board {
+        [
+            (BoardCoord(2), BOrW::white(1)),
+            (BoardCoord(6), BOrW::white(1)),
+            (BoardCoord(20), BOrW::black(2)),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_visits() {
+        let moves = Simd1MoveGenerator::gen_unique_moves(
+            &Board::default(),
+            Dice(Die(3), Die(1)),
+            Bw::White,
+        );
+
+        for selection in [Selection::Uct { c: 1.0 }, Selection::Puct { c: 1.5 }] {
+            let settings = MctsSettings {
+                budget: Budget::Iterations(300),
+                selection,
+                ..Default::default()
+            };
+            let mut mcts = Mcts::new(PipCountEvaluator, settings, rand::thread_rng());
+
+            let stats = mcts.analyze(Bw::White, moves.clone());
+
+            assert_eq!(stats.len(), moves.len());
+            assert_eq!(stats.iter().map(|s| s.visits).sum::<u32>(), 300);
+        }
+    }
+
+    #[test]
+    fn test_bears_off() {
+        let moves =
+            Simd1MoveGenerator::gen_unique_moves(&bearoff(), Dice(Die(6), Die(2)), Bw::White);
+
+        let mut mcts = Mcts::new(
+            PipCountEvaluator,
+            MctsSettings {
+                budget: Budget::Time(Duration::from_millis(20)),
+                random_playouts: true,
+                ..Default::default()
+            },
+            rand::thread_rng(),
+        );
+
+        assert_eq!(
+            mcts.choose(Bw::White, moves.clone()).winner(),
+            Some(Bw::White)
+        );
+
+        let mut mcts = mcts.with_policy(GreedyDecision(PipCountEvaluator));
+        assert_eq!(mcts.choose(Bw::White, moves).winner(), Some(Bw::White));
+    }
+
+    #[test]
+    fn test_beats_random() {
+        let mut wins = 0;
+
+        for seed in 0..10 {
+            // the dice, then each side
+            let rng = |stream: u64| StdRng::seed_from_u64(seed * 4 + stream);
+            let mut game = Game::new(&mut rng(0));
+
+            let settings = MctsSettings {
+                budget: Budget::Iterations(30),
+                ..Default::default()
+            };
+
+            game.play(
+                rng(1),
+                &mut Mcts::new(PipCountEvaluator, settings, rng(2)),
+                &mut RandomMoveDecision(rng(3)),
+            )
+            .for_each(drop);
+
+            if game.result().unwrap().winner == Bw::White {
+                wins += 1;
+            }
+        }
+
+        assert!(wins >= 6, "{} wins out of 10", wins);
+    }
+}
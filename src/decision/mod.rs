@@ -1,11 +1,25 @@
 use rand::seq::SliceRandom;
 
-use crate::types::{board::Board, prim::Bw};
+pub mod greedy;
+pub mod mcts;
+
+use crate::{
+    game::Game,
+    types::{board::Board, prim::Bw},
+};
 
 pub trait MoveDecision {
     fn choose(&mut self, player: Bw, moves: Vec<Board>) -> Board;
 }
 
+pub trait CubeDecision {
+    /// whether `player` doubles before rolling
+    fn double(&mut self, player: Bw, game: &Game) -> bool;
+
+    /// whether `player` takes the double offered to them
+    fn take(&mut self, player: Bw, game: &Game) -> bool;
+}
+
 pub struct RandomMoveDecision<R>(pub R);
 
 impl<R: rand::Rng> MoveDecision for RandomMoveDecision<R> {
@@ -13,3 +27,13 @@ impl<R: rand::Rng> MoveDecision for RandomMoveDecision<R> {
         moves.choose(&mut self.0).unwrap().clone()
     }
 }
+
+impl<R: rand::Rng> CubeDecision for RandomMoveDecision<R> {
+    fn double(&mut self, _player: Bw, _game: &Game) -> bool {
+        self.0.gen()
+    }
+
+    fn take(&mut self, _player: Bw, _game: &Game) -> bool {
+        self.0.gen()
+    }
+}
@@ -0,0 +1,553 @@
+use std::{
+    ops::{Range, RangeInclusive},
+    simd::{Mask, Simd, SimdInt, SimdOrd, SimdPartialEq, SimdPartialOrd},
+};
+
+use crate::types::{board::Board, prim::Bw};
+
+use super::{contact, Contact, Encoder, Perspective, TdGammon};
+
+const LANES: usize = 32;
+
+type Lanes = Simd<i8, LANES>;
+
+#[derive(Clone)]
+/// up to 32 boards: board `i` of the chunk is element `i` of every lane
+struct Chunk {
+    lanes: [Lanes; 26],
+    /// whether White is on roll
+    white: Mask<i8, LANES>,
+}
+
+impl Chunk {
+    fn empty() -> Self {
+        Self {
+            lanes: [Lanes::splat(0); 26],
+            white: Mask::splat(false),
+        }
+    }
+
+    /// checkers of `color` by point from its own side like `encode::counts`,
+    /// 1..=24 on the board, 25 on the bar, 0 borne off
+    #[inline]
+    fn counts(&self, color: Bw) -> [Lanes; 26] {
+        let mut counts = [Lanes::splat(0); 26];
+
+        for (point, n) in counts.iter_mut().enumerate().skip(1) {
+            *n = match color {
+                Bw::White => self.lanes[point],
+                Bw::Black => -self.lanes[25 - point],
+            }
+            .simd_max(Lanes::splat(0));
+        }
+
+        counts[0] = Lanes::splat(15) - counts[1..].iter().fold(Lanes::splat(0), |a, b| a + b);
+        counts
+    }
+
+    /// per point from the side of the player on roll, positive for their checkers
+    #[inline]
+    fn perspective(&self) -> [Lanes; 26] {
+        let mut lanes = [Lanes::splat(0); 26];
+
+        for (i, lane) in lanes.iter_mut().enumerate() {
+            *lane = self.white.select(self.lanes[i], -self.lanes[25 - i]);
+        }
+
+        lanes
+    }
+}
+
+#[inline]
+fn ones(mask: Mask<i8, LANES>) -> Simd<f32, LANES> {
+    mask.select(Lanes::splat(1), Lanes::splat(0)).cast()
+}
+
+#[inline]
+fn ratio(n: Lanes, d: f32) -> Simd<f32, LANES> {
+    n.cast::<f32>() / Simd::splat(d)
+}
+
+/// The features come a chunk at a time, a vector per feature, and go out a
+/// board at a time: writes the rows of `features` that are `rows` of `out`.
+#[inline]
+fn transpose(out: &mut [f32], features: &[Simd<f32, LANES>], rows: Range<usize>) {
+    let f = features.len();
+
+    for (i, row) in out[rows.start * f..rows.end * f]
+        .chunks_exact_mut(f)
+        .enumerate()
+    {
+        for (val, feature) in row.iter_mut().zip(features) {
+            *val = feature[i];
+        }
+    }
+}
+
+/// how many of `masks` are set, lane by lane
+#[inline]
+fn count(masks: impl Iterator<Item = Mask<i8, LANES>>) -> Lanes {
+    masks.fold(Lanes::splat(0), |n, mask| {
+        n + mask.select(Lanes::splat(1), Lanes::splat(0))
+    })
+}
+
+/// the rearmost of `points` where `has` holds, 0 where there's none
+#[inline]
+fn rearmost(
+    counts: &[Lanes; 26],
+    points: RangeInclusive<usize>,
+    has: impl Fn(Lanes) -> Mask<i8, LANES>,
+) -> Lanes {
+    points.fold(Lanes::splat(0), |back, p| {
+        has(counts[p]).select(Lanes::splat(p as i8), back)
+    })
+}
+
+/// `Contact::features` of the side with `own`, against `opp`
+fn contact_features(own: &[Lanes; 26], opp: &[Lanes; 26], out: &mut [Simd<f32, LANES>]) {
+    use contact::*;
+
+    type Wide = Simd<i16, LANES>;
+
+    // the sums of pips don't fit in an i8
+    let wide = |n: Lanes| n.cast::<i16>();
+    let made = |n: Lanes| n.simd_ge(Lanes::splat(2));
+
+    // the opponent's checker on its point q is on our 25 - q, none is 25
+    let opp_back = Lanes::splat(25) - rearmost(opp, 1..=25, |n| n.simd_gt(Lanes::splat(0)));
+
+    let break_contact = (1..=25).fold(Wide::splat(0), |sum, p| {
+        let past = (Wide::splat(p as i16) - wide(opp_back)).simd_max(Wide::splat(0));
+        sum + wide(own[p]) * past
+    });
+    out[BREAK_CONTACT] = break_contact.cast::<f32>() / Simd::splat(152.0);
+
+    let back = rearmost(own, 1..=25, |n| n.simd_gt(Lanes::splat(0)));
+    out[BACK_CHECKER] = ratio(back, 25.0);
+    out[BACK_ANCHOR] = ratio(rearmost(own, 1..=24, made), 24.0);
+
+    // the foremost, so from the back
+    let forward = (19..=24).rev().fold(Lanes::splat(0), |anchor, p| {
+        made(own[p]).select(Lanes::splat(25 - p as i8), anchor)
+    });
+    out[FORWARD_ANCHOR] = ratio(forward, 6.0);
+
+    let mut prime = Lanes::splat(0);
+    let mut run = Lanes::splat(0);
+    for n in &own[1..=24] {
+        run = made(*n).select(run + Lanes::splat(1), Lanes::splat(0));
+        prime = prime.simd_max(run);
+    }
+    out[PRIME] = ratio(prime.simd_min(Lanes::splat(6)), 6.0);
+
+    out[HOME_POINTS] = ratio(count(own[1..=6].iter().map(|&n| made(n))), 6.0);
+    out[BLOTS] = ratio(
+        count(own[1..=24].iter().map(|n| n.simd_eq(Lanes::splat(1)))),
+        15.0,
+    );
+
+    // none closed is 0 too, where there's nothing on the bar
+    let closed = own[25]
+        .simd_gt(Lanes::splat(0))
+        .select(count(opp[1..=6].iter().map(|&n| made(n))), Lanes::splat(0));
+    let closed = ratio(closed, 6.0);
+    out[ENTER] = closed * closed;
+
+    let pips = (1..=25).fold(Wide::splat(0), |sum, p| {
+        sum + wide(own[p]) * Wide::splat(p as i16)
+    });
+    out[PIPS] = pips.cast::<f32>() / Simd::splat(167.0);
+    out[OFF] = ratio(own[0], 15.0);
+}
+
+/// Many boards in struct-of-arrays layout, 32 to a SIMD vector.
+///
+/// Every board comes with the player on roll. Features come out as one flat
+/// `[len, features]` buffer, in the same layout the `Encoder`s use for a
+/// single board.
+#[derive(Clone)]
+pub struct BoardBatch {
+    chunks: Vec<Chunk>,
+    len: usize,
+}
+
+impl BoardBatch {
+    pub fn new() -> Self {
+        Self {
+            chunks: vec![],
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            chunks: Vec::with_capacity(capacity.div_ceil(LANES)),
+            len: 0,
+        }
+    }
+
+    /// e.g. the afterstates from `MoveGen::gen_unique_moves`, with the opponent on roll
+    pub fn from_boards(boards: &[Board], player: Bw) -> Self {
+        let mut batch = Self::with_capacity(boards.len());
+
+        for board in boards {
+            batch.push(board, player);
+        }
+
+        batch
+    }
+
+    pub fn push(&mut self, board: &Board, player: Bw) {
+        let i = self.len % LANES;
+
+        if i == 0 {
+            self.chunks.push(Chunk::empty());
+        }
+
+        let chunk = self.chunks.last_mut().unwrap();
+
+        for (lane, val) in chunk.lanes.iter_mut().zip(board.0.as_array()) {
+            lane[i] = *val;
+        }
+        chunk.white.set(i, player == Bw::White);
+
+        self.len += 1;
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    pub fn get(&self, i: usize) -> (Board, Bw) {
+        assert!(i < self.len, "board {} out of {}", i, self.len);
+
+        let chunk = &self.chunks[i / LANES];
+        let mut board = Board::empty();
+
+        for (val, lane) in board.0.as_mut_array().iter_mut().zip(&chunk.lanes) {
+            *val = lane[i % LANES];
+        }
+
+        let player = if chunk.white.test(i % LANES) {
+            Bw::White
+        } else {
+            Bw::Black
+        };
+
+        (board, player)
+    }
+
+    /// the chunks with the rows they hold
+    fn chunks(&self) -> impl Iterator<Item = (&Chunk, Range<usize>)> {
+        self.chunks.iter().enumerate().map(|(c, chunk)| {
+            let start = c * LANES;
+            (chunk, start..self.len.min(start + LANES))
+        })
+    }
+
+    fn resize(&self, out: &mut Vec<f32>, features: usize) {
+        out.clear();
+        out.resize(self.len * features, 0.0);
+    }
+
+    /// same as `TdGammon::encode` on every board
+    pub fn encode_td_gammon(&self, out: &mut Vec<f32>) {
+        const F: usize = TdGammon::SIZE;
+        self.resize(out, F);
+        let mut features = [Simd::splat(0.0); F];
+
+        for (chunk, rows) in self.chunks() {
+            let mut put = |feature, vals| features[feature] = vals;
+
+            for (color, base) in [(Bw::White, 0), (Bw::Black, 98)] {
+                let counts = chunk.counts(color);
+
+                for (point, &n) in counts[1..=24].iter().enumerate() {
+                    let i = base + point * 4;
+
+                    put(i, ones(n.simd_ge(Lanes::splat(1))));
+                    put(i + 1, ones(n.simd_ge(Lanes::splat(2))));
+                    put(i + 2, ones(n.simd_ge(Lanes::splat(3))));
+                    put(
+                        i + 3,
+                        ratio((n - Lanes::splat(3)).simd_max(Lanes::splat(0)), 2.0),
+                    );
+                }
+
+                put(base + 96, ratio(counts[25], 2.0));
+                put(base + 97, ratio(counts[0], 15.0));
+            }
+
+            put(196, ones(chunk.white));
+            put(197, ones(!chunk.white));
+
+            transpose(out, &features, rows);
+        }
+    }
+
+    /// same as `Perspective::encode` on every board
+    pub fn encode_perspective(&self, out: &mut Vec<f32>) {
+        const F: usize = Perspective::SIZE;
+        self.resize(out, F);
+        let mut features = [Simd::splat(0.0); F];
+
+        for (chunk, rows) in self.chunks() {
+            let mut put = |feature, vals| features[feature] = vals;
+
+            let lanes = chunk.perspective();
+            let white = chunk.counts(Bw::White);
+            let black = chunk.counts(Bw::Black);
+
+            for (i, &n) in lanes[1..=24].iter().enumerate() {
+                put(i, ratio(n, 15.0));
+            }
+
+            // bar, then borne off
+            for (i, lane) in [25, 0].into_iter().enumerate() {
+                let own = chunk.white.select(white[lane], black[lane]);
+                let opp = chunk.white.select(black[lane], white[lane]);
+
+                put(24 + i * 2, ratio(own, 15.0));
+                put(25 + i * 2, ratio(opp, 15.0));
+            }
+
+            transpose(out, &features, rows);
+        }
+    }
+
+    /// same as `Contact::encode` on every board
+    pub fn encode_contact(&self, out: &mut Vec<f32>) {
+        const F: usize = Contact::SIZE;
+        const SIDE: usize = Contact::SIDE;
+        self.resize(out, F);
+        let mut features = [Simd::splat(0.0); F];
+
+        for (chunk, rows) in self.chunks() {
+            let white = chunk.counts(Bw::White);
+            let black = chunk.counts(Bw::Black);
+            let own = std::array::from_fn(|p| chunk.white.select(white[p], black[p]));
+            let opp = std::array::from_fn(|p| chunk.white.select(black[p], white[p]));
+
+            for (a, b, side) in [(&own, &opp, 0), (&opp, &own, SIDE)] {
+                // GNUBG's units, like `gnubg_units`
+                for (point, &n) in a[1..=25].iter().enumerate() {
+                    let i = side + point * 4;
+
+                    features[i] = ones(n.simd_eq(Lanes::splat(1)));
+                    features[i + 1] = ones(n.simd_eq(Lanes::splat(2)));
+                    features[i + 2] = ones(n.simd_ge(Lanes::splat(3)));
+                    features[i + 3] = ratio((n - Lanes::splat(3)).simd_max(Lanes::splat(0)), 2.0);
+                }
+
+                contact_features(a, b, &mut features[side + 100..side + SIDE]);
+            }
+
+            transpose(out, &features, rows);
+        }
+    }
+
+    /// Any other encoder: plain scalar code, getting each board out of the
+    /// batch and encoding it on its own. Much slower than the `encode_*`s.
+    pub fn encode_scalar<E: Encoder>(&self, out: &mut Vec<f32>) {
+        self.resize(out, E::SIZE);
+
+        for (i, row) in out.chunks_mut(E::SIZE).enumerate() {
+            let (board, player) = self.get(i);
+            E::encode(&board, player, row);
+        }
+    }
+
+    /// pips of the player on roll and of the opponent
+    pub fn pip_counts(&self) -> Vec<(u16, u16)> {
+        let mut pips = Vec::with_capacity(self.len);
+
+        for (chunk, rows) in self.chunks() {
+            let [white, black] = [Bw::White, Bw::Black].map(|color| {
+                chunk
+                    .counts(color)
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .fold(Simd::<i16, LANES>::splat(0), |acc, (point, n)| {
+                        acc + n.cast::<i16>() * Simd::splat(point as i16)
+                    })
+            });
+
+            for i in 0..rows.len() {
+                let (white, black) = (white[i] as u16, black[i] as u16);
+
+                pips.push(if chunk.white.test(i) {
+                    (white, black)
+                } else {
+                    (black, white)
+                });
+            }
+        }
+
+        pips
+    }
+
+    /// boards where someone has borne off all their checkers
+    pub fn finished_mask(&self) -> Vec<bool> {
+        let mut mask = Vec::with_capacity(self.len);
+
+        for (chunk, rows) in self.chunks() {
+            let fifteen = Lanes::splat(15);
+            let finished = chunk.counts(Bw::White)[0].simd_eq(fifteen)
+                | chunk.counts(Bw::Black)[0].simd_eq(fifteen);
+
+            mask.extend(finished.to_array()[..rows.len()].iter());
+        }
+
+        mask
+    }
+
+    /// boards where some checkers still have to pass the opponent's
+    pub fn contact_mask(&self) -> Vec<bool> {
+        let mut mask = Vec::with_capacity(self.len);
+
+        for (chunk, rows) in self.chunks() {
+            // White's rearmost lane and Black's, which move the other way
+            let mut white_back = Lanes::splat(0);
+            let mut black_back = Lanes::splat(26);
+
+            for (lane, vals) in chunk.lanes.iter().enumerate() {
+                let lane = Lanes::splat(lane as i8);
+
+                white_back = vals.simd_gt(Lanes::splat(0)).select(lane, white_back);
+                black_back = vals
+                    .simd_lt(Lanes::splat(0))
+                    .select(lane.simd_min(black_back), black_back);
+            }
+
+            let contact = white_back.simd_gt(black_back);
+            mask.extend(contact.to_array()[..rows.len()].iter());
+        }
+
+        mask
+    }
+}
+
+impl Default for BoardBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> FromIterator<(&'a Board, Bw)> for BoardBatch {
+    fn from_iter<T: IntoIterator<Item = (&'a Board, Bw)>>(iter: T) -> Self {
+        let mut batch = Self::new();
+
+        for (board, player) in iter {
+            batch.push(board, player);
+        }
+
+        batch
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::{
+        encode::{Contact, Encoder, Perspective, TdGammon},
+        types::{
+            board::{Board, BoardCoord},
+            prim::Bw,
+        },
+    };
+
+    use super::BoardBatch;
+
+    fn random_batch(n: usize) -> (Vec<(Board, Bw)>, BoardBatch) {
+        let rng = &mut rand::thread_rng();
+
+        let boards = (0..n).map(|_| (rng.gen(), rng.gen())).collect::<Vec<_>>();
+        let batch = boards.iter().map(|(b, p)| (b, *p)).collect();
+
+        (boards, batch)
+    }
+
+    fn per_board<E: Encoder>(boards: &[(Board, Bw)]) -> Vec<f32> {
+        boards
+            .iter()
+            .flat_map(|(board, player)| E::encode_vec(board, *player))
+            .collect()
+    }
+
+    #[test]
+    fn test_same_as_encoders() {
+        for n in [0, 1, 31, 32, 33, 100] {
+            let (boards, batch) = random_batch(n);
+            let mut out = vec![];
+
+            assert_eq!(batch.len(), n);
+
+            batch.encode_td_gammon(&mut out);
+            assert_eq!(out, per_board::<TdGammon>(&boards));
+
+            batch.encode_perspective(&mut out);
+            assert_eq!(out, per_board::<Perspective>(&boards));
+
+            batch.encode_contact(&mut out);
+            assert_eq!(out, per_board::<Contact>(&boards));
+
+            batch.encode_scalar::<Contact>(&mut out);
+            assert_eq!(out, per_board::<Contact>(&boards));
+
+            for (i, (board, player)) in boards.iter().enumerate() {
+                assert_eq!(&batch.get(i), &(board.clone(), *player));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pips_and_masks() {
+        let (boards, batch) = random_batch(100);
+
+        let pips = |board: &Board, color: Bw| {
+            (1..=25u8)
+                .map(|p| board[BoardCoord::rel(color, p)])
+                .map(|v| if v.matches(color) { v.to_count() } else { 0 })
+                .zip(1u16..)
+                .map(|(n, p)| n as u16 * p)
+                .sum::<u16>()
+        };
+
+        let pip_counts = batch.pip_counts();
+        let finished = batch.finished_mask();
+        let contact = batch.contact_mask();
+
+        for (i, (board, player)) in boards.iter().enumerate() {
+            assert_eq!(pip_counts[i], (pips(board, *player), pips(board, -*player)));
+            assert_eq!(finished[i], board.winner().is_some());
+
+            let back = |color: Bw| {
+                (1..=25u8)
+                    .rev()
+                    .find(|p| board[BoardCoord::rel(color, *p)].matches(color))
+                    .unwrap_or(0)
+            };
+            // the rearmost checkers have passed each other
+            assert_eq!(contact[i], back(Bw::White) + back(Bw::Black) > 25);
+        }
+
+        assert_eq!(
+            BoardBatch::from_boards(&[Board::default()], Bw::White).pip_counts(),
+            vec![(167, 167)]
+        );
+    }
+}
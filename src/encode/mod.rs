@@ -0,0 +1,322 @@
+//! Neural-net inputs.
+//!
+//! Every encoder writes into the first `SIZE` floats of a caller-supplied
+//! slice, so one buffer can be reused (or sliced out of a batch) without
+//! allocating per board.
+
+use crate::types::{
+    board::{Board, BoardCoord},
+    prim::Bw,
+};
+
+pub mod batch;
+
+pub trait Encoder {
+    /// number of features written
+    const SIZE: usize;
+
+    /// Writes the features of `board` with `player` on roll into `out[..Self::SIZE]`.
+    fn encode(board: &Board, player: Bw, out: &mut [f32]);
+
+    fn encode_vec(board: &Board, player: Bw) -> Vec<f32> {
+        let mut out = vec![0.0; Self::SIZE];
+        Self::encode(board, player, &mut out);
+        out
+    }
+}
+
+/// Checkers of `color` by point, numbered from its own side: 1..=24 on the
+/// board, 25 on the bar and 0 borne off.
+#[inline]
+fn counts(board: &Board, color: Bw) -> [u8; 26] {
+    let mut counts = [0; 26];
+
+    for point in 1..=25 {
+        let val = board[BoardCoord::rel(color, point)];
+
+        if val.matches(color) {
+            counts[point as usize] = val.to_count();
+        }
+    }
+
+    counts[0] = 15u8.saturating_sub(counts.iter().sum());
+    counts
+}
+
+/// Tesauro's four units per point: at least one, two and three checkers,
+/// then half of every checker past the third
+#[inline]
+fn td_units(n: u8, out: &mut [f32]) {
+    out[0] = (n >= 1) as u8 as f32;
+    out[1] = (n >= 2) as u8 as f32;
+    out[2] = (n >= 3) as u8 as f32;
+    out[3] = n.saturating_sub(3) as f32 / 2.0;
+}
+
+/// GNUBG's variant: exactly one, exactly two, at least three, then the rest
+#[inline]
+fn gnubg_units(n: u8, out: &mut [f32]) {
+    out[0] = (n == 1) as u8 as f32;
+    out[1] = (n == 2) as u8 as f32;
+    out[2] = (n >= 3) as u8 as f32;
+    out[3] = n.saturating_sub(3) as f32 / 2.0;
+}
+
+/// The TD-Gammon 198 inputs.
+///
+/// White's 98 inputs come first, then Black's: 4 units for each point from
+/// the color's own 1 to 24, checkers on the bar / 2 and borne off / 15.
+/// The last two say whether White or Black is on roll.
+pub struct TdGammon;
+
+impl Encoder for TdGammon {
+    const SIZE: usize = 198;
+
+    fn encode(board: &Board, player: Bw, out: &mut [f32]) {
+        let out = &mut out[..Self::SIZE];
+
+        for (color, side) in [Bw::White, Bw::Black].into_iter().zip(out.chunks_mut(98)) {
+            let counts = counts(board, color);
+
+            for point in 1..=24 {
+                td_units(counts[point], &mut side[(point - 1) * 4..point * 4]);
+            }
+
+            side[96] = counts[25] as f32 / 2.0;
+            side[97] = counts[0] as f32 / 15.0;
+        }
+
+        out[196] = (player == Bw::White) as u8 as f32;
+        out[197] = (player == Bw::Black) as u8 as f32;
+    }
+}
+
+/// Hand-crafted features of one side in `Contact`, each roughly in `0..=1`.
+pub mod contact {
+    /// pips the side needs to move to get all its checkers past the opponent's
+    pub const BREAK_CONTACT: usize = 0;
+    /// the side's rearmost checker
+    pub const BACK_CHECKER: usize = 1;
+    /// the side's rearmost point with two or more checkers
+    pub const BACK_ANCHOR: usize = 2;
+    /// how far forward the side's best anchor in the opponent's home board is, 0 without one
+    pub const FORWARD_ANCHOR: usize = 3;
+    /// longest run of made points
+    pub const PRIME: usize = 4;
+    /// made points in the side's home board
+    pub const HOME_POINTS: usize = 5;
+    pub const BLOTS: usize = 6;
+    /// chance of not entering from the bar, 0 with nothing on the bar
+    pub const ENTER: usize = 7;
+    pub const PIPS: usize = 8;
+    pub const OFF: usize = 9;
+
+    pub const FEATURES: usize = 10;
+}
+
+/// A GNUBG-style contact encoding, relative to the player on roll.
+///
+/// For each side, player on roll first: GNUBG's 4 units for points 1..=24 and
+/// the bar (25), numbered from that side, then the `contact` features.
+pub struct Contact;
+
+impl Contact {
+    const SIDE: usize = 25 * 4 + contact::FEATURES;
+
+    fn features(own: &[u8; 26], opp: &[u8; 26], out: &mut [f32]) {
+        use contact::*;
+
+        // the opponent's checker on its point q is on our 25 - q
+        let opp_back = (1..=25).rev().find(|&q| opp[q] > 0).map_or(25, |q| 25 - q);
+
+        let made = |p: usize| own[p] >= 2;
+
+        out[BREAK_CONTACT] = (opp_back + 1..=25)
+            .map(|p| own[p] as u32 * (p - opp_back) as u32)
+            .sum::<u32>() as f32
+            / 152.0;
+
+        out[BACK_CHECKER] = (1..=25).rev().find(|&p| own[p] > 0).unwrap_or(0) as f32 / 25.0;
+        out[BACK_ANCHOR] = (1..=24).rev().find(|&p| made(p)).unwrap_or(0) as f32 / 24.0;
+        out[FORWARD_ANCHOR] = (19..=24).find(|&p| made(p)).map_or(0, |p| 25 - p) as f32 / 6.0;
+
+        let mut prime = 0;
+        let mut run = 0;
+        for p in 1..=24 {
+            run = if made(p) { run + 1 } else { 0 };
+            prime = prime.max(run);
+        }
+        out[PRIME] = prime.min(6) as f32 / 6.0;
+
+        out[HOME_POINTS] = (1..=6).filter(|&p| made(p)).count() as f32 / 6.0;
+        out[BLOTS] = (1..=24).filter(|&p| own[p] == 1).count() as f32 / 15.0;
+
+        out[ENTER] = if own[25] > 0 {
+            // we enter on our 19..=24, the opponent's home board
+            let closed = (1..=6).filter(|&q| opp[q] >= 2).count() as f32 / 6.0;
+            closed * closed
+        } else {
+            0.0
+        };
+
+        out[PIPS] = (1..=25).map(|p| own[p] as u32 * p as u32).sum::<u32>() as f32 / 167.0;
+        out[OFF] = own[0] as f32 / 15.0;
+    }
+}
+
+impl Encoder for Contact {
+    const SIZE: usize = 2 * Self::SIDE;
+
+    fn encode(board: &Board, player: Bw, out: &mut [f32]) {
+        let out = &mut out[..Self::SIZE];
+        let own = counts(board, player);
+        let opp = counts(board, -player);
+
+        for ((a, b), side) in [(&own, &opp), (&opp, &own)]
+            .into_iter()
+            .zip(out.chunks_mut(Self::SIDE))
+        {
+            for point in 1..=25 {
+                gnubg_units(a[point], &mut side[(point - 1) * 4..point * 4]);
+            }
+
+            Self::features(a, b, &mut side[100..]);
+        }
+    }
+}
+
+/// A compact encoding of the board as the player on roll sees it.
+///
+/// Black's boards are flipped with `Board::inverse` first, so the player on
+/// roll always moves from 24 to 1. Points 1..=24 hold their checkers / 15,
+/// positive for the player on roll and negative for the opponent, followed by
+/// the player's and the opponent's checkers on the bar, then borne off, / 15.
+pub struct Perspective;
+
+impl Encoder for Perspective {
+    const SIZE: usize = 28;
+
+    fn encode(board: &Board, player: Bw, out: &mut [f32]) {
+        let out = &mut out[..Self::SIZE];
+
+        let board = board.canonical(player).into_board();
+
+        for point in 1..=24 {
+            out[point - 1] = board.0[point] as f32 / 15.0;
+        }
+
+        let own = counts(&board, Bw::White);
+        let opp = counts(&board, Bw::Black);
+
+        out[24] = own[25] as f32 / 15.0;
+        out[25] = opp[25] as f32 / 15.0;
+        out[26] = own[0] as f32 / 15.0;
+        out[27] = opp[0] as f32 / 15.0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::types::{
+        board::{Board, BoardCoord},
+        prim::{BOrW, Bw},
+    };
+
+    use super::{contact, Contact, Encoder, Perspective, TdGammon};
+
+    #[test]
+    fn test_td_gammon() {
+        let board = Board::default();
+        let white = TdGammon::encode_vec(&board, Bw::White);
+
+        assert_eq!(white.len(), 198);
+        // two on White's 24, five on its 6 and 13
+        assert_eq!(&white[23 * 4..24 * 4], &[1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(&white[5 * 4..6 * 4], &[1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(&white[12 * 4..13 * 4], &[1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(&white[196..], &[1.0, 0.0]);
+
+        // the starting position is the same for both
+        assert_eq!(&white[..98], &white[98..196]);
+
+        let black = TdGammon::encode_vec(&board, Bw::Black);
+        assert_eq!(&black[..196], &white[..196]);
+        assert_eq!(&black[196..], &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_td_gammon_bar_and_off() {
+        let board: Board = [
+            (BoardCoord::bar(Bw::White), BOrW::white(2)),
+            (BoardCoord(1), BOrW::white(3)),
+            (BoardCoord(20), BOrW::black(15)),
+        ]
+        .into_iter()
+        .collect();
+
+        let out = TdGammon::encode_vec(&board, Bw::White);
+
+        assert_eq!(out[96], 1.0);
+        assert_eq!(out[97], 10.0 / 15.0);
+        assert_eq!(out[98 + 96], 0.0);
+        assert_eq!(out[98 + 97], 0.0);
+    }
+
+    #[test]
+    fn test_contact() {
+        let out = Contact::encode_vec(&Board::default(), Bw::White);
+        let side = Contact::SIZE / 2;
+
+        assert_eq!(out.len(), 220);
+        assert_eq!(&out[..side], &out[side..]);
+
+        let features = &out[100..side];
+        assert_eq!(features[contact::BACK_CHECKER], 24.0 / 25.0);
+        assert_eq!(features[contact::BACK_ANCHOR], 1.0);
+        assert_eq!(features[contact::FORWARD_ANCHOR], 1.0 / 6.0);
+        assert_eq!(features[contact::HOME_POINTS], 1.0 / 6.0);
+        assert_eq!(features[contact::PIPS], 1.0);
+        assert_eq!(features[contact::ENTER], 0.0);
+        assert_eq!(features[contact::OFF], 0.0);
+    }
+
+    #[test]
+    fn test_symmetric() {
+        let rng = &mut rand::thread_rng();
+
+        for _ in 0..1000 {
+            let board: Board = rng.gen();
+            let inverse = board.inverse();
+
+            for player in [Bw::White, Bw::Black] {
+                assert_eq!(
+                    Perspective::encode_vec(&board, player),
+                    Perspective::encode_vec(&inverse, -player)
+                );
+                assert_eq!(
+                    Contact::encode_vec(&board, player),
+                    Contact::encode_vec(&inverse, -player)
+                );
+
+                let td = TdGammon::encode_vec(&board, player);
+                let td_inverse = TdGammon::encode_vec(&inverse, -player);
+                assert_eq!(&td[..98], &td_inverse[98..196]);
+                assert_eq!(&td[98..196], &td_inverse[..98]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_perspective() {
+        let board = Board::default();
+
+        let white = Perspective::encode_vec(&board, Bw::White);
+        assert_eq!(white[5], 5.0 / 15.0);
+        assert_eq!(white[0], -2.0 / 15.0);
+        assert_eq!(&white[24..], &[0.0; 4]);
+
+        assert_eq!(white, Perspective::encode_vec(&board, Bw::Black));
+    }
+}
@@ -0,0 +1,50 @@
+//! Errors for everything a caller can get wrong, so bad input from outside
+//! doesn't have to panic.
+
+use crate::types::board::BoardError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BgError {
+    /// the game is over, nothing more can happen in it
+    GameOver,
+    /// moving, or skipping a move, before the dice are rolled
+    NotRolled,
+    /// the board isn't one of those the dice can be played to
+    IllegalMove,
+    /// skipping a move while there's one to make
+    MustMove,
+    /// dice only go from 1 to 6
+    InvalidDie(u8),
+    /// not one of the 36 characters of `Dice::as_alnum`
+    InvalidDice(char),
+    InvalidBoard(BoardError),
+}
+
+impl std::fmt::Display for BgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BgError::GameOver => write!(f, "the game is over"),
+            BgError::NotRolled => write!(f, "the dice haven't been rolled"),
+            BgError::IllegalMove => write!(f, "not a legal move"),
+            BgError::MustMove => write!(f, "there is a legal move to make"),
+            BgError::InvalidDie(n) => write!(f, "invalid die {}", n),
+            BgError::InvalidDice(c) => write!(f, "invalid dice {:?}", c),
+            BgError::InvalidBoard(err) => write!(f, "invalid board: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BgError::InvalidBoard(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BoardError> for BgError {
+    fn from(err: BoardError) -> Self {
+        BgError::InvalidBoard(err)
+    }
+}
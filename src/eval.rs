@@ -0,0 +1,196 @@
+use std::ops::{Add, AddAssign, Mul};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    board::Board,
+    prim::{Bw, WinKind},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+/// Chances of one player, like GNUBG's five outputs: gammons include
+/// backgammons, and the chance of losing is `1 - win`.
+pub struct Probabilities {
+    pub win: f32,
+    pub win_gammon: f32,
+    pub win_backgammon: f32,
+    pub lose_gammon: f32,
+    pub lose_backgammon: f32,
+}
+
+impl Probabilities {
+    /// what a finished game on `board` is worth to `player`
+    pub fn finished(board: &Board, player: Bw) -> Option<Probabilities> {
+        let winner = board.winner()?;
+        let kind = board.win_kind(winner);
+
+        let won = Probabilities {
+            win: 1.0,
+            win_gammon: (kind >= WinKind::Gammon) as u8 as f32,
+            win_backgammon: (kind >= WinKind::Backgammon) as u8 as f32,
+            lose_gammon: 0.0,
+            lose_backgammon: 0.0,
+        };
+
+        Some(if winner == player { won } else { won.invert() })
+    }
+
+    #[inline]
+    pub fn lose(&self) -> f32 {
+        1.0 - self.win
+    }
+
+    /// the same chances, seen by the opponent
+    #[must_use]
+    #[inline]
+    pub fn invert(&self) -> Probabilities {
+        Probabilities {
+            win: self.lose(),
+            win_gammon: self.lose_gammon,
+            win_backgammon: self.lose_backgammon,
+            lose_gammon: self.win_gammon,
+            lose_backgammon: self.win_backgammon,
+        }
+    }
+
+    /// expected points per game at a 1-cube nobody can turn
+    #[inline]
+    pub fn equity(&self) -> f32 {
+        self.win - self.lose() + self.win_gammon - self.lose_gammon + self.win_backgammon
+            - self.lose_backgammon
+    }
+}
+
+impl Add for Probabilities {
+    type Output = Probabilities;
+
+    #[inline]
+    fn add(self, rhs: Probabilities) -> Self::Output {
+        Probabilities {
+            win: self.win + rhs.win,
+            win_gammon: self.win_gammon + rhs.win_gammon,
+            win_backgammon: self.win_backgammon + rhs.win_backgammon,
+            lose_gammon: self.lose_gammon + rhs.lose_gammon,
+            lose_backgammon: self.lose_backgammon + rhs.lose_backgammon,
+        }
+    }
+}
+
+impl AddAssign for Probabilities {
+    #[inline]
+    fn add_assign(&mut self, rhs: Probabilities) {
+        *self = *self + rhs;
+    }
+}
+
+/// for weighted averages
+impl Mul<f32> for Probabilities {
+    type Output = Probabilities;
+
+    #[inline]
+    fn mul(self, rhs: f32) -> Self::Output {
+        Probabilities {
+            win: self.win * rhs,
+            win_gammon: self.win_gammon * rhs,
+            win_backgammon: self.win_backgammon * rhs,
+            lose_gammon: self.lose_gammon * rhs,
+            lose_backgammon: self.lose_backgammon * rhs,
+        }
+    }
+}
+
+pub trait Evaluator {
+    /// Chances of `player`, who's on roll, in a game that isn't finished yet.
+    fn evaluate(&self, board: &Board, player: Bw) -> Probabilities;
+
+    /// Chances of `player` after they've moved to `board`, so with the opponent
+    /// on roll. Exact when the move finishes the game.
+    fn evaluate_after(&self, board: &Board, player: Bw) -> Probabilities {
+        Probabilities::finished(board, player)
+            .unwrap_or_else(|| self.evaluate(board, -player).invert())
+    }
+}
+
+impl<F: Fn(&Board, Bw) -> Probabilities> Evaluator for F {
+    fn evaluate(&self, board: &Board, player: Bw) -> Probabilities {
+        self(board, player)
+    }
+}
+
+/// A crude guess from the pip counts alone, as if every position were a race.
+/// No gammons.
+pub struct PipCountEvaluator;
+
+impl Evaluator for PipCountEvaluator {
+    fn evaluate(&self, board: &Board, player: Bw) -> Probabilities {
+        let own = board.pip_count(player) as f32;
+        let opp = board.pip_count(-player) as f32;
+
+        // being on roll is worth about half a roll
+        let lead = opp - own + 4.0;
+        let spread = (own + opp).sqrt() + 1.0;
+
+        Probabilities {
+            win: 1.0 / (1.0 + (-lead / spread).exp()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::{
+        board::{Board, BoardCoord},
+        prim::{BOrW, Bw},
+    };
+
+    use super::{Evaluator, PipCountEvaluator, Probabilities};
+
+    #[test]
+    fn test_equity() {
+        let p = Probabilities {
+            win: 0.6,
+            win_gammon: 0.2,
+            win_backgammon: 0.05,
+            lose_gammon: 0.1,
+            lose_backgammon: 0.0,
+        };
+
+        assert!((p.equity() - 0.35).abs() < 1e-6);
+        assert!((p.invert().equity() + p.equity()).abs() < 1e-6);
+        assert_eq!(p.invert().invert(), p);
+    }
+
+    #[test]
+    fn test_finished() {
+        let board: Board = [
+            (BoardCoord(3), BOrW::black(1)),
+            (BoardCoord(20), BOrW::black(14)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(Probabilities::finished(&Board::default(), Bw::White), None);
+
+        let white = Probabilities::finished(&board, Bw::White).unwrap();
+        assert_eq!(white.equity(), 3.0);
+        assert_eq!(
+            Probabilities::finished(&board, Bw::Black).unwrap().equity(),
+            -3.0
+        );
+
+        // finished games never reach the evaluator
+        let panics = |_: &Board, _: Bw| -> Probabilities { unreachable!() };
+        assert_eq!(panics.evaluate_after(&board, Bw::Black), white.invert());
+    }
+
+    #[test]
+    fn test_pip_count() {
+        let board = Board::default();
+
+        let white = PipCountEvaluator.evaluate(&board, Bw::White);
+        assert!(white.win > 0.5);
+        assert_eq!(white, PipCountEvaluator.evaluate(&board, Bw::Black));
+        assert!(PipCountEvaluator.evaluate_after(&board, Bw::White).win < 0.5);
+    }
+}
@@ -0,0 +1,520 @@
+//! GNU Backgammon Position ID and Match ID.
+//!
+//! GNUBG numbers its players 0 and 1; here that's `Bw::as_u8`, so Black is
+//! player 0 and White is player 1. The Position ID is always relative to the
+//! player on roll: the opponent's 25 lanes (points 1..=24 from their side, then
+//! the bar) come first, then the player on roll's.
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::{
+    game::{Cube, Game, GameResult, GameState},
+    matchplay::MatchScore,
+    types::{
+        board::{Board, PACKED_SIZE},
+        dice::{Dice, Die},
+        prim::{BAndW, Bw},
+    },
+};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut acc = 0u32;
+    let mut bits = 0;
+
+    for byte in bytes {
+        acc = acc << 8 | *byte as u32;
+        bits += 8;
+
+        while bits >= 6 {
+            bits -= 6;
+            out.push(BASE64[(acc >> bits) as usize & 0x3f] as char);
+        }
+    }
+
+    if bits > 0 {
+        out.push(BASE64[(acc << (6 - bits)) as usize & 0x3f] as char);
+    }
+
+    out
+}
+
+fn decode_base64(s: &str, len: usize) -> anyhow::Result<Vec<u8>> {
+    let chars = (len * 8).div_ceil(6);
+
+    if s.len() != chars {
+        bail!("expected {} characters, got {:?}", chars, s);
+    }
+
+    let mut out = Vec::with_capacity(len);
+    let mut acc = 0u32;
+    let mut bits = 0;
+
+    for c in s.bytes() {
+        let v = BASE64
+            .iter()
+            .position(|b| *b == c)
+            .ok_or_else(|| anyhow!("invalid character {:?} in {:?}", c as char, s))?;
+
+        acc = acc << 6 | v as u32;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            if out.len() < len {
+                out.push((acc >> bits) as u8);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+#[inline]
+fn get_bit(key: &[u8], bit: usize) -> bool {
+    key[bit / 8] & (1 << (bit % 8)) != 0
+}
+
+#[inline]
+fn set_bit(key: &mut [u8], bit: usize) {
+    key[bit / 8] |= 1 << (bit % 8);
+}
+
+fn get_bits(key: &[u8], start: usize, n: usize) -> u32 {
+    (0..n).fold(0, |acc, i| acc | (get_bit(key, start + i) as u32) << i)
+}
+
+fn set_bits(key: &mut [u8], start: usize, n: usize, value: u32) {
+    for i in 0..n {
+        if value & (1 << i) != 0 {
+            set_bit(key, start + i);
+        }
+    }
+}
+
+impl Board {
+    /// the packed board, which has White on roll, seen from `on_roll`
+    pub fn to_gnubg_position_id(&self, on_roll: Bw) -> String {
+        let key = match on_roll {
+            Bw::White => self.to_packed(),
+            Bw::Black => self.inverse().to_packed(),
+        };
+
+        encode_base64(&key)
+    }
+
+    pub fn from_gnubg_position_id(id: &str, on_roll: Bw) -> anyhow::Result<Board> {
+        let key = decode_base64(id, PACKED_SIZE)?;
+        let board = Board::from_packed(&key.try_into().unwrap())
+            .with_context(|| format!("invalid position id {:?}", id))?;
+
+        Ok(match on_roll {
+            Bw::White => board,
+            Bw::Black => board.inverse(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GnubgGameState {
+    NoGame = 0,
+    Playing = 1,
+    Over = 2,
+    Resigned = 3,
+    DroppedCube = 4,
+}
+
+impl TryFrom<u32> for GnubgGameState {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> anyhow::Result<Self> {
+        match value {
+            0 => Ok(GnubgGameState::NoGame),
+            1 => Ok(GnubgGameState::Playing),
+            2 => Ok(GnubgGameState::Over),
+            3 => Ok(GnubgGameState::Resigned),
+            4 => Ok(GnubgGameState::DroppedCube),
+            _ => Err(anyhow!("invalid game state {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// everything a GNUBG Match ID carries
+pub struct MatchId {
+    pub cube: u16,
+    /// `None` for a centered cube
+    pub cube_owner: Option<Bw>,
+    pub dice_owner: Bw,
+    pub crawford: bool,
+    pub game_state: GnubgGameState,
+    /// who has to make a decision, e.g. the one a double is offered to
+    pub turn: Bw,
+    pub double_offered: bool,
+    /// 0 for none, then 1, 2, 3 for single, gammon, backgammon
+    pub resignation: u8,
+    /// `None` if not rolled yet
+    pub dice: Option<Dice>,
+    /// 0 for money games
+    pub match_length: u16,
+    /// indexed by `Bw::as_u8`
+    pub score: [u16; 2],
+}
+
+impl MatchId {
+    pub fn encode(&self) -> String {
+        let mut key = [0u8; 9];
+
+        set_bits(&mut key, 0, 4, self.cube.trailing_zeros());
+        set_bits(
+            &mut key,
+            4,
+            2,
+            self.cube_owner.map(|bw| bw.as_u8() as u32).unwrap_or(3),
+        );
+        set_bits(&mut key, 6, 1, self.dice_owner.as_u8() as u32);
+        set_bits(&mut key, 7, 1, self.crawford as u32);
+        set_bits(&mut key, 8, 3, self.game_state as u32);
+        set_bits(&mut key, 11, 1, self.turn.as_u8() as u32);
+        set_bits(&mut key, 12, 1, self.double_offered as u32);
+        set_bits(&mut key, 13, 2, self.resignation as u32);
+
+        if let Some(dice) = self.dice {
+            set_bits(&mut key, 15, 3, dice.0 .0 as u32);
+            set_bits(&mut key, 18, 3, dice.1 .0 as u32);
+        }
+
+        set_bits(&mut key, 21, 15, self.match_length as u32);
+        set_bits(&mut key, 36, 15, self.score[0] as u32);
+        set_bits(&mut key, 51, 15, self.score[1] as u32);
+
+        encode_base64(&key)
+    }
+
+    pub fn decode(id: &str) -> anyhow::Result<MatchId> {
+        let key = decode_base64(id, 9)?;
+
+        let player = |bit: u32| if bit == 0 { Bw::Black } else { Bw::White };
+
+        let cube_owner = match get_bits(&key, 4, 2) {
+            3 => None,
+            2 => bail!("invalid cube owner in match id {:?}", id),
+            bit => Some(player(bit)),
+        };
+
+        let dice = match (get_bits(&key, 15, 3), get_bits(&key, 18, 3)) {
+            (0, 0) => None,
+            (a @ 1..=6, b @ 1..=6) => Some(Dice(Die(a as u8), Die(b as u8))),
+            _ => bail!("invalid dice in match id {:?}", id),
+        };
+
+        Ok(MatchId {
+            cube: 1 << get_bits(&key, 0, 4),
+            cube_owner,
+            dice_owner: player(get_bits(&key, 6, 1)),
+            crawford: get_bits(&key, 7, 1) != 0,
+            game_state: get_bits(&key, 8, 3).try_into()?,
+            turn: player(get_bits(&key, 11, 1)),
+            double_offered: get_bits(&key, 12, 1) != 0,
+            resignation: get_bits(&key, 13, 2) as u8,
+            dice,
+            match_length: get_bits(&key, 21, 15) as u16,
+            score: [get_bits(&key, 36, 15) as u16, get_bits(&key, 51, 15) as u16],
+        })
+    }
+
+    /// no cube is a centered 1-cube here
+    pub fn from_game(game: &Game) -> MatchId {
+        let cube = game.cube.unwrap_or_default();
+        let score = game.match_score;
+
+        let (dice_owner, turn, dice) = match game.state {
+            GameState::BeforeRoll(player) => (player, player, None),
+            GameState::DoubleOffered(player) => (player, -player, None),
+            GameState::Dice(player, dice) => (player, player, Some(dice)),
+            GameState::Finished(result) => (result.winner, result.winner, None),
+        };
+
+        let game_state = match game.state {
+            GameState::Finished(_) => GnubgGameState::Over,
+            _ => GnubgGameState::Playing,
+        };
+
+        MatchId {
+            cube: cube.value as u16,
+            cube_owner: cube.owner,
+            dice_owner,
+            crawford: score.is_some_and(|s| s.crawford),
+            game_state,
+            turn,
+            double_offered: matches!(game.state, GameState::DoubleOffered(_)),
+            resignation: 0,
+            dice,
+            match_length: score.map_or(0, |s| s.length as u16),
+            score: score.map_or([0, 0], |s| {
+                [
+                    s.score.get(&Bw::Black) as u16,
+                    s.score.get(&Bw::White) as u16,
+                ]
+            }),
+        }
+    }
+
+    pub fn game_cube(&self) -> Cube {
+        Cube {
+            value: self.cube as u32,
+            owner: self.cube_owner,
+        }
+    }
+
+    /// `None` for money games
+    pub fn match_score(&self) -> anyhow::Result<Option<MatchScore>> {
+        if self.match_length == 0 {
+            return Ok(None);
+        }
+
+        let points =
+            |n: u16| u8::try_from(n).map_err(|_| anyhow!("{} points are too many for a match", n));
+
+        Ok(Some(MatchScore {
+            length: points(self.match_length)?,
+            score: BAndW::new(points(self.score[0])?, points(self.score[1])?),
+            crawford: self.crawford,
+        }))
+    }
+
+    /// the state of a game on `board`, if `Game` can represent it
+    pub fn game_state(&self, board: &Board) -> anyhow::Result<GameState> {
+        match self.game_state {
+            GnubgGameState::Playing if self.double_offered => {
+                Ok(GameState::DoubleOffered(self.dice_owner))
+            }
+            GnubgGameState::Playing => match self.dice {
+                Some(dice) => Ok(GameState::Dice(self.turn, dice)),
+                None => Ok(GameState::BeforeRoll(self.turn)),
+            },
+            GnubgGameState::Over => board
+                .winner()
+                .map(|winner| {
+                    let kind = board.win_kind(winner);
+                    GameState::Finished(GameResult::new(winner, kind, self.cube as u32))
+                })
+                .ok_or_else(|| anyhow!("game is over, but nobody has won on the board")),
+            state => bail!("game state {:?} is not supported", state),
+        }
+    }
+}
+
+impl Game {
+    /// Position ID and Match ID. The position is seen by the player on roll,
+    /// not by `turn`, who differs from them while a double is offered.
+    pub fn to_gnubg_ids(&self) -> (String, String) {
+        let match_id = MatchId::from_game(self);
+
+        (
+            self.board.to_gnubg_position_id(match_id.dice_owner),
+            match_id.encode(),
+        )
+    }
+
+    pub fn from_gnubg_ids(position_id: &str, match_id: &str) -> anyhow::Result<Game> {
+        let match_id = MatchId::decode(match_id)?;
+        let board = Board::from_gnubg_position_id(position_id, match_id.dice_owner)?;
+        let state = match_id.game_state(&board)?;
+        let match_score = match_id.match_score()?;
+
+        Ok(Game {
+            board,
+            state,
+            cube: match match_score {
+                Some(score) if score.crawford => None,
+                _ => Some(match_id.game_cube()),
+            },
+            match_score,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::{
+        game::{Game, GameState},
+        matchplay::MatchScore,
+        types::{
+            board::{Board, BoardCoord},
+            dice::{Dice, Die},
+            prim::{BAndW, BOrW, Bw},
+        },
+    };
+
+    use super::{GnubgGameState, MatchId};
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::default();
+
+        assert_eq!(board.to_gnubg_position_id(Bw::White), "4HPwATDgc/ABMA");
+        assert_eq!(board.to_gnubg_position_id(Bw::Black), "4HPwATDgc/ABMA");
+        assert_eq!(
+            Board::from_gnubg_position_id("4HPwATDgc/ABMA", Bw::Black).unwrap(),
+            board
+        );
+    }
+
+    #[test]
+    fn test_position_roundtrip() {
+        let rng = &mut rand::thread_rng();
+
+        for _ in 0..1000 {
+            let board: Board = rng.gen();
+            let player: Bw = rng.gen();
+
+            let id = board.to_gnubg_position_id(player);
+
+            assert_eq!(id.len(), 14);
+            assert_eq!(Board::from_gnubg_position_id(&id, player).unwrap(), board);
+        }
+    }
+
+    #[test]
+    fn test_position_bar() {
+        let board: Board = [
+            (BoardCoord::bar(Bw::White), BOrW::white(1)),
+            (BoardCoord(6), BOrW::white(14)),
+            (BoardCoord(19), BOrW::black(15)),
+        ]
+        .into_iter()
+        .collect();
+
+        let id = board.to_gnubg_position_id(Bw::White);
+
+        assert_eq!(
+            Board::from_gnubg_position_id(&id, Bw::White).unwrap(),
+            board
+        );
+        assert_ne!(
+            Board::from_gnubg_position_id(&id, Bw::Black).unwrap(),
+            board
+        );
+    }
+
+    #[test]
+    fn test_invalid_position_ids() {
+        assert!(Board::from_gnubg_position_id("4HPwATDgc/ABM", Bw::White).is_err());
+        assert!(Board::from_gnubg_position_id("4HPwATDgc/AB!A", Bw::White).is_err());
+        assert!(Board::from_gnubg_position_id("//////////////", Bw::White).is_err());
+    }
+
+    #[test]
+    fn test_match_id() {
+        // 9 point match, 2-4, player 0 owns a 2-cube, player 1 has rolled 52
+        let id = MatchId {
+            cube: 2,
+            cube_owner: Some(Bw::Black),
+            dice_owner: Bw::White,
+            crawford: false,
+            game_state: GnubgGameState::Playing,
+            turn: Bw::White,
+            double_offered: false,
+            resignation: 0,
+            dice: Some(Dice(Die(5), Die(2))),
+            match_length: 9,
+            score: [2, 4],
+        };
+
+        assert_eq!(id.encode(), "QYkqASAAIAAA");
+        assert_eq!(MatchId::decode("QYkqASAAIAAA").unwrap(), id);
+
+        let money = MatchId::decode("cAgAAAAAAAAA").unwrap();
+        assert_eq!(money.cube, 1);
+        assert_eq!(money.cube_owner, None);
+        assert_eq!(money.game_state, GnubgGameState::NoGame);
+    }
+
+    #[test]
+    fn test_cube_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        let mut game = Game::with_cube(rng);
+        game.skip_move_by_necessity(rng);
+        game.offer_double();
+
+        let (position_id, match_id) = game.to_gnubg_ids();
+        let back = Game::from_gnubg_ids(&position_id, &match_id).unwrap();
+
+        assert_eq!(back.board, game.board);
+        assert_eq!(back.cube, game.cube);
+        assert_eq!(back.state.to_string(), game.state.to_string());
+
+        game.take_cube(rng);
+
+        let (position_id, match_id) = game.to_gnubg_ids();
+        let back = Game::from_gnubg_ids(&position_id, &match_id).unwrap();
+
+        assert_eq!(back.cube, game.cube);
+        assert_eq!(back.state.to_string(), game.state.to_string());
+    }
+
+    #[test]
+    fn test_double_offered() {
+        // money game, White on roll has doubled and Black is to take or drop:
+        // Black has all 15 on their 1 point, White all 15 on their 6 point
+        let (position_id, match_id) = ("/38AAADg/w8AAA", "cBEAAAAAAAAA");
+
+        let game = Game::from_gnubg_ids(position_id, match_id).unwrap();
+        assert_eq!(game.board[BoardCoord(6)], BOrW::white(15));
+        assert_eq!(game.board[BoardCoord(24)], BOrW::black(15));
+        assert!(matches!(game.state, GameState::DoubleOffered(Bw::White)));
+
+        let id = MatchId::decode(match_id).unwrap();
+        assert_eq!(id.dice_owner, Bw::White);
+        assert_eq!(id.turn, Bw::Black);
+        assert!(id.double_offered);
+
+        assert_eq!(
+            game.to_gnubg_ids(),
+            (position_id.to_string(), match_id.to_string())
+        );
+    }
+
+    #[test]
+    fn test_game_roundtrip() {
+        let mut game = Game::new(&mut rand::thread_rng());
+        game.state = GameState::Dice(Bw::Black, Dice(Die(3), Die(1)));
+
+        let (position_id, match_id) = game.to_gnubg_ids();
+        let back = Game::from_gnubg_ids(&position_id, &match_id).unwrap();
+
+        assert_eq!(back.board, game.board);
+        assert!(matches!(
+            back.state,
+            GameState::Dice(Bw::Black, Dice(Die(3), Die(1)))
+        ));
+
+        let game = Game::from_gnubg_ids(&position_id, "QYkqASAAIAAA").unwrap();
+        let score = game.match_score.unwrap();
+
+        assert_eq!(score.length, 9);
+        assert_eq!(score.score, BAndW::new(2, 4));
+        assert_eq!(game.to_gnubg_ids().1, "QYkqASAAIAAA");
+    }
+
+    #[test]
+    fn test_crawford() {
+        let mut game = Game::new(&mut rand::thread_rng());
+        game.match_score = Some(MatchScore {
+            length: 5,
+            score: BAndW::new(4, 1),
+            crawford: true,
+        });
+
+        let (position_id, match_id) = game.to_gnubg_ids();
+        let back = Game::from_gnubg_ids(&position_id, &match_id).unwrap();
+
+        assert_eq!(back.match_score, game.match_score);
+        assert_eq!(back.cube, None);
+    }
+}
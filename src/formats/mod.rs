@@ -0,0 +1,2 @@
+pub mod gnubg;
+pub mod xgid;
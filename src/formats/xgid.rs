@@ -0,0 +1,345 @@
+//! eXtreme Gammon IDs, e.g. `XGID=-b----E-C---eE---c-e----B-:0:0:1:52:0:0:0:0:10`.
+//!
+//! XG's bottom player (uppercase letters) moves from 24 down to 1 with the bar
+//! at 25, just like `Bw::White`, so the 26 position characters map straight
+//! onto `Board` lanes 0..=25.
+
+use anyhow::{anyhow, bail};
+use itertools::Itertools;
+
+use crate::{
+    game::{Cube, Game, GameState},
+    matchplay::MatchScore,
+    types::{
+        board::{Board, BoardCoord},
+        dice::{Dice, Die},
+        prim::{BAndW, BOrW, Bw},
+    },
+};
+
+fn xgid_char(val: BOrW) -> char {
+    match val.to_bwn() {
+        None => '-',
+        Some((Bw::White, n)) => (b'A' + n - 1) as char,
+        Some((Bw::Black, n)) => (b'a' + n - 1) as char,
+    }
+}
+
+fn parse_xgid_char(c: char) -> anyhow::Result<BOrW> {
+    match c {
+        '-' => Ok(BOrW::empty()),
+        'A'..='O' => Ok(BOrW::white(c as u8 - b'A' + 1)),
+        'a'..='o' => Ok(BOrW::black(c as u8 - b'a' + 1)),
+        _ => Err(anyhow!("invalid position character {:?}", c)),
+    }
+}
+
+/// The cube limit, as a power of 2. Games here don't have one, so this is the
+/// only one that's written or read.
+const MAX_CUBE: i32 = 10;
+
+fn xgid_player(bw: Bw) -> i8 {
+    match bw {
+        Bw::White => 1,
+        Bw::Black => -1,
+    }
+}
+
+impl Board {
+    /// just the 26-character position part of an XGID
+    pub fn to_xgid_position(&self) -> String {
+        (0..=25).map(|i| xgid_char(self[BoardCoord(i)])).collect()
+    }
+
+    pub fn from_xgid_position(s: &str) -> anyhow::Result<Board> {
+        if s.chars().count() != 26 {
+            bail!("position {:?} should have 26 characters", s);
+        }
+
+        let board = s
+            .chars()
+            .enumerate()
+            .map(|(i, c)| Ok((BoardCoord(i as u8), parse_xgid_char(c)?)))
+            .collect::<anyhow::Result<Board>>()?;
+
+        for player in [Bw::White, Bw::Black] {
+            if board[BoardCoord::off(player)].matches(player) {
+                bail!("{} checkers on the opponent's bar in {:?}", player, s);
+            }
+
+            let total: u32 = (0..=25)
+                .map(|i| board[BoardCoord(i)])
+                .filter(|v| v.matches(player))
+                .map(|v| v.to_count() as u32)
+                .sum();
+
+            if total > 15 {
+                bail!("{} has {} checkers in {:?}", player, total, s);
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+impl Game {
+    /// Fails for finished games, which XGIDs can't describe, and cubes over 2^10.
+    /// With a double offered, the turn is the doubler's.
+    pub fn to_xgid(&self) -> anyhow::Result<String> {
+        let (player, dice) = match self.state {
+            GameState::BeforeRoll(player) => (player, "00".to_string()),
+            GameState::DoubleOffered(player) => (player, "D".to_string()),
+            GameState::Dice(player, dice) => (player, format!("{}{}", dice.0 .0, dice.1 .0)),
+            GameState::Finished(_) => bail!("finished games have no XGID"),
+        };
+
+        let cube = self.cube.unwrap_or_default();
+        if cube.value > 1 << MAX_CUBE {
+            bail!("cube {} is over XGID's limit", cube.value);
+        }
+
+        let (score_x, score_o, crawford, length) = match self.match_score {
+            Some(score) => (
+                score.score.get(&Bw::White),
+                score.score.get(&Bw::Black),
+                score.crawford as u8,
+                score.length,
+            ),
+            None => (0, 0, 0, 0),
+        };
+
+        Ok(format!(
+            "XGID={}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+            self.board.to_xgid_position(),
+            cube.value.trailing_zeros(),
+            cube.owner.map_or(0, xgid_player),
+            xgid_player(player),
+            dice,
+            score_x,
+            score_o,
+            crawford,
+            length,
+            MAX_CUBE
+        ))
+    }
+
+    /// Fails on what a `Game` can't hold: beavers, the Jacoby rule, a cube limit.
+    pub fn from_xgid(xgid: &str) -> anyhow::Result<Game> {
+        let xgid = xgid.trim();
+        let xgid = xgid.strip_prefix("XGID=").unwrap_or(xgid);
+
+        let Some((
+            position,
+            cube,
+            cube_owner,
+            turn,
+            dice,
+            score_x,
+            score_o,
+            flags,
+            length,
+            max_cube,
+        )) = xgid.split(':').collect_tuple()
+        else {
+            bail!("XGID {:?} should have 10 fields", xgid);
+        };
+
+        let board = Board::from_xgid_position(position)?;
+
+        let number = |name: &str, s: &str| {
+            s.parse::<i32>()
+                .map_err(|_| anyhow!("invalid {} {:?} in XGID", name, s))
+        };
+
+        let player = match number("turn", turn)? {
+            1 => Bw::White,
+            -1 => Bw::Black,
+            _ => bail!("invalid turn {:?} in XGID", turn),
+        };
+
+        let cube = Cube {
+            value: match number("cube value", cube)? {
+                exp @ 0..=MAX_CUBE => 1 << exp,
+                _ => bail!("invalid cube value {:?} in XGID", cube),
+            },
+            owner: match number("cube position", cube_owner)? {
+                0 => None,
+                1 => Some(Bw::White),
+                -1 => Some(Bw::Black),
+                _ => bail!("invalid cube position {:?} in XGID", cube_owner),
+            },
+        };
+
+        let points = |name: &str, s: &str| {
+            s.parse::<u8>()
+                .map_err(|_| anyhow!("invalid {} {:?} in XGID", name, s))
+        };
+
+        if number("max cube", max_cube)? != MAX_CUBE {
+            bail!("cube limit {:?} in XGID is not supported", max_cube);
+        }
+
+        // the flags are Crawford in matches, Jacoby and beavers in money games,
+        // which aren't played here
+        let flags = number("Crawford/Jacoby flags", flags)?;
+        let match_score = match (points("match length", length)?, flags) {
+            (0, 0) => None,
+            (0, _) => bail!("Jacoby and beavers (flags {}) are not supported", flags),
+            (length, 0 | 1) => Some(MatchScore {
+                length,
+                score: BAndW::new(points("score", score_o)?, points("score", score_x)?),
+                crawford: flags == 1,
+            }),
+            _ => bail!("invalid Crawford flag {} in XGID", flags),
+        };
+
+        let state = match dice.as_bytes() {
+            [a @ b'1'..=b'6', b @ b'1'..=b'6'] => GameState::Dice(
+                player,
+                Dice(Die::try_from(a - b'0')?, Die::try_from(b - b'0')?),
+            ),
+            b"00" => GameState::BeforeRoll(player),
+            b"D" => GameState::DoubleOffered(player),
+            b"B" | b"R" => bail!("beavers and raccoons are not supported"),
+            _ => bail!("invalid dice {:?} in XGID", dice),
+        };
+
+        Ok(Game {
+            board,
+            state,
+            cube: match match_score {
+                Some(score) if score.crawford => None,
+                _ => Some(cube),
+            },
+            match_score,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::{
+        game::{Cube, Game, GameState},
+        matchplay::MatchScore,
+        types::{
+            board::{Board, BoardCoord},
+            dice::{Dice, Die},
+            prim::{BAndW, BOrW, Bw},
+        },
+    };
+
+    #[test]
+    fn test_starting_position() {
+        assert_eq!(
+            Board::default().to_xgid_position(),
+            "-b----E-C---eE---c-e----B-"
+        );
+
+        let xgid = "XGID=-b----E-C---eE---c-e----B-:0:0:1:52:0:0:0:0:10";
+        let game = Game::from_xgid(xgid).unwrap();
+
+        assert_eq!(game.board, Board::default());
+        assert!(matches!(
+            game.state,
+            GameState::Dice(Bw::White, Dice(Die(5), Die(2)))
+        ));
+        assert_eq!(game.to_xgid().unwrap(), xgid);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let rng = &mut rand::thread_rng();
+
+        for _ in 0..1000 {
+            let game = Game {
+                board: rng.gen(),
+                state: GameState::Dice(rng.gen(), rng.gen()),
+                cube: None,
+                match_score: None,
+            };
+
+            let back = Game::from_xgid(&game.to_xgid().unwrap()).unwrap();
+
+            assert_eq!(back.board, game.board);
+            assert_eq!(back.state.to_string(), game.state.to_string());
+        }
+    }
+
+    #[test]
+    fn test_cube() {
+        let game = Game::from_xgid("XGID=-b----E-C---eE---c-e----B-:1:-1:1:D:0:0:0:0:10").unwrap();
+
+        assert_eq!(
+            game.cube,
+            Some(Cube {
+                value: 2,
+                owner: Some(Bw::Black)
+            })
+        );
+        assert!(matches!(game.state, GameState::DoubleOffered(Bw::White)));
+        assert_eq!(
+            game.to_xgid().unwrap(),
+            "XGID=-b----E-C---eE---c-e----B-:1:-1:1:D:0:0:0:0:10"
+        );
+
+        let game = Game::from_xgid("-b----E-C---eE---c-e----B-:0:0:-1:00:0:0:0:0:10").unwrap();
+        assert!(matches!(game.state, GameState::BeforeRoll(Bw::Black)));
+    }
+
+    #[test]
+    fn test_match() {
+        let xgid = "XGID=-b----E-C---eE---c-e----B-:0:0:-1:52:4:2:1:5:10";
+        let game = Game::from_xgid(xgid).unwrap();
+
+        assert_eq!(
+            game.match_score,
+            Some(MatchScore {
+                length: 5,
+                score: BAndW::new(2, 4),
+                crawford: true
+            })
+        );
+        assert_eq!(game.cube, None);
+        assert_eq!(game.to_xgid().unwrap(), xgid);
+    }
+
+    #[test]
+    fn test_bars() {
+        let mut board = Board::default();
+        board[BoardCoord(24)] = BOrW::white(1);
+        board[BoardCoord::bar(Bw::White)] = BOrW::white(1);
+        board[BoardCoord(1)] = BOrW::black(1);
+        board[BoardCoord::bar(Bw::Black)] = BOrW::black(1);
+
+        assert_eq!(board.to_xgid_position(), "aa----E-C---eE---c-e----AA");
+        assert_eq!(
+            Board::from_xgid_position("aa----E-C---eE---c-e----AA").unwrap(),
+            board
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let position = "-b----E-C---eE---c-e----B-";
+
+        for xgid in [
+            format!("{position}:1:2:-1:63:0:0:0:0:10"),
+            format!("{position}:0:0:1:B:0:0:0:0:10"),
+            format!("{position}:0:0:1:52:2:1:0:300:10"),
+            format!("{position}:0:0:2:52:0:0:0:0:10"),
+            // Jacoby, beavers and both in a money game
+            format!("{position}:0:0:1:52:0:0:1:0:10"),
+            format!("{position}:0:0:1:52:0:0:2:0:10"),
+            format!("{position}:0:0:1:52:0:0:3:0:10"),
+            format!("{position}:0:0:1:52:0:0:2:5:10"),
+            format!("{position}:0:0:1:52:0:0:0:0:8"),
+            format!("{position}:0:0:1:52:0:0:0:0"),
+            "A-b----E-C---eE---c-e----B:0:0:1:52:0:0:0:0:10".to_string(),
+            "-p----E-C---eE---c-e----B-:0:0:1:52:0:0:0:0:10".to_string(),
+        ] {
+            assert!(Game::from_xgid(&xgid).is_err(), "{}", xgid);
+        }
+    }
+}
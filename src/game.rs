@@ -1,17 +1,44 @@
 use std::marker::PhantomData;
 
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    decision::MoveDecision,
+    decision::{CubeDecision, MoveDecision},
+    error::BgError,
+    matchplay::MatchScore,
     movegen::{simd::Simd1MoveGenerator, MoveGen},
-    types::{board::Board, dice::Dice, prim::Bw},
+    types::{
+        board::Board,
+        dice::Dice,
+        prim::{Bw, WinKind},
+    },
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cube {
+    pub value: u32,
+    /// `None` while the cube is in the middle
+    pub owner: Option<Bw>,
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Self {
+            value: 1,
+            owner: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Game {
     pub board: Board,
     pub state: GameState,
+    /// `None` when doubling isn't allowed at all
+    pub cube: Option<Cube>,
+    /// `None` for money games
+    pub match_score: Option<MatchScore>,
 }
 
 impl Game {
@@ -30,6 +57,29 @@ impl Game {
         Self {
             board: Board::default(),
             state: GameState::Dice(player, dice),
+            cube: None,
+            match_score: None,
+        }
+    }
+
+    /// a money game with a centered cube
+    pub fn with_cube(rng: &mut impl Rng) -> Self {
+        Self {
+            cube: Some(Cube::default()),
+            ..Self::new(rng)
+        }
+    }
+
+    #[inline]
+    pub fn cube_value(&self) -> u32 {
+        self.cube.map_or(1, |cube| cube.value)
+    }
+
+    #[inline]
+    pub fn may_double(&self, player: Bw) -> bool {
+        match self.cube {
+            Some(cube) => cube.owner.unwrap_or(player) == player,
+            None => false,
         }
     }
 
@@ -40,36 +90,124 @@ impl Game {
                 // Simd1MoveGenerator::gen_unique_moves(&self.board, dice, player)
                 // BasicMoveGenerator::gen_unique_moves(&self.board, dice, player)
             }
+            GameState::BeforeRoll(_) | GameState::DoubleOffered(_) => {
+                panic!("cannot get moves before rolling")
+            }
             GameState::Finished(_) => panic!("cannot get moves on finished game"),
         }
     }
 
+    /// `next_moves` for games from outside: errors instead of panicking, and
+    /// checks the board and dice first
+    pub fn try_next_moves<Generator: MoveGen>(&self) -> Result<Vec<Board>, BgError> {
+        match self.state {
+            GameState::Dice(player, dice) => {
+                self.board.validate()?;
+                dice.validate()?;
+                Ok(Generator::gen_unique_moves(&self.board, dice, player))
+            }
+            GameState::BeforeRoll(_) | GameState::DoubleOffered(_) => Err(BgError::NotRolled),
+            GameState::Finished(_) => Err(BgError::GameOver),
+        }
+    }
+
+    /// whoever is next either gets a chance to double or rolls right away
+    fn pass_turn(&mut self, rng: &mut impl Rng, player: Bw) {
+        let next = -player;
+
+        if self.may_double(next) {
+            self.state = GameState::BeforeRoll(next);
+        } else {
+            self.state = GameState::Dice(next, Dice::roll(rng));
+        }
+    }
+
+    pub fn roll(&mut self, rng: &mut impl Rng) {
+        let GameState::BeforeRoll(player) = self.state else {
+            panic!("can only roll before rolling");
+        };
+
+        self.state = GameState::Dice(player, Dice::roll(rng));
+    }
+
+    pub fn offer_double(&mut self) {
+        let GameState::BeforeRoll(player) = self.state else {
+            panic!("can only double before rolling");
+        };
+
+        assert!(self.may_double(player), "{} may not double", player);
+
+        self.state = GameState::DoubleOffered(player);
+    }
+
+    /// the cube is turned over to the taker and the doubler rolls
+    pub fn take_cube(&mut self, rng: &mut impl Rng) {
+        let GameState::DoubleOffered(player) = self.state else {
+            panic!("no double to take");
+        };
+
+        let cube = self.cube.as_mut().expect("double offered without a cube");
+        cube.value *= 2;
+        cube.owner = Some(-player);
+
+        self.state = GameState::Dice(player, Dice::roll(rng));
+    }
+
+    /// the doubler wins the current cube value
+    pub fn drop_cube(&mut self) {
+        let GameState::DoubleOffered(player) = self.state else {
+            panic!("no double to drop");
+        };
+
+        self.state =
+            GameState::Finished(GameResult::new(player, WinKind::Single, self.cube_value()));
+    }
+
     pub fn make_move_unchecked(&mut self, rng: &mut impl Rng, new_board: Board) {
         let GameState::Dice(player, _) = self.state else {
-            panic!("cannot make move on finished game");
+            panic!("cannot make move without dice");
         };
 
         self.board = new_board;
 
         if let Some(player) = self.board.winner() {
-            self.state = GameState::Finished(player);
+            let kind = self.board.win_kind(player);
+            self.state = GameState::Finished(GameResult::new(player, kind, self.cube_value()));
         } else {
-            let dice = Dice::roll(rng);
-            self.state = GameState::Dice(-player, dice);
+            self.pass_turn(rng, player);
         }
     }
 
     pub fn skip_move_by_necessity(&mut self, rng: &mut impl Rng) {
         let GameState::Dice(player, _) = self.state else {
-            panic!("cannot skip move on finished game");
+            panic!("cannot skip move without dice");
         };
 
-        // if let Some(player) = self.board.winner() {
-        //     self.state = GameState::Finished(player);
-        // } else {
+        self.pass_turn(rng, player);
+    }
 
-        let dice = Dice::roll(rng);
-        self.state = GameState::Dice(-player, dice);
+    /// `make_move_unchecked` once `new_board` is known to be one of the
+    /// `next_moves`
+    pub fn make_move(&mut self, rng: &mut impl Rng, new_board: Board) -> Result<(), BgError> {
+        if !self
+            .try_next_moves::<Simd1MoveGenerator>()?
+            .contains(&new_board)
+        {
+            return Err(BgError::IllegalMove);
+        }
+
+        self.make_move_unchecked(rng, new_board);
+        Ok(())
+    }
+
+    /// `skip_move_by_necessity` once it's known there's no move to make
+    pub fn skip_move(&mut self, rng: &mut impl Rng) -> Result<(), BgError> {
+        if !self.try_next_moves::<Simd1MoveGenerator>()?.is_empty() {
+            return Err(BgError::MustMove);
+        }
+
+        self.skip_move_by_necessity(rng);
+        Ok(())
     }
 
     pub fn play<'a, R: Rng, White: MoveDecision, Black: MoveDecision>(
@@ -83,10 +221,34 @@ impl Game {
             rng,
             white,
             black: Some(black),
+            cube: None,
             generator: PhantomData::<Simd1MoveGenerator>,
         }
     }
 
+    /// like `play`, also asking the players about doubling when the game has a cube
+    pub fn play_cube<
+        'a,
+        R: Rng,
+        White: MoveDecision + CubeDecision,
+        Black: MoveDecision + CubeDecision,
+    >(
+        &'a mut self,
+        rng: R,
+        white: &'a mut White,
+        black: &'a mut Black,
+    ) -> GamePlay<'a, R, White, Black> {
+        GamePlay {
+            cube: Some(CubeAgents {
+                white_double: White::double,
+                white_take: White::take,
+                black_double: Black::double,
+                black_take: Black::take,
+            }),
+            ..self.play(rng, white, black)
+        }
+    }
+
     pub fn play_self<'a, R: Rng, White: MoveDecision>(
         &'a mut self,
         rng: R,
@@ -97,18 +259,49 @@ impl Game {
             rng,
             white,
             black: None,
+            cube: None,
             generator: PhantomData::<Simd1MoveGenerator>,
         }
     }
 
+    pub fn play_self_cube<'a, R: Rng, White: MoveDecision + CubeDecision>(
+        &'a mut self,
+        rng: R,
+        white: &'a mut White,
+    ) -> GamePlay<'a, R, White> {
+        GamePlay {
+            cube: Some(CubeAgents {
+                white_double: White::double,
+                white_take: White::take,
+                black_double: White::double,
+                black_take: White::take,
+            }),
+            ..self.play_self(rng, white)
+        }
+    }
+
     pub fn is_finished(&self) -> bool {
+        matches!(self.state, GameState::Finished(_))
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
         match self.state {
-            GameState::Finished(_) => true,
-            GameState::Dice(_, _) => false,
+            GameState::Finished(result) => Some(result),
+            _ => None,
         }
     }
 }
 
+type CubeFn<Agent> = fn(&mut Agent, Bw, &Game) -> bool;
+
+/// `CubeDecision` of both players, kept apart so cubeless agents can still play
+struct CubeAgents<White, Black> {
+    white_double: CubeFn<White>,
+    white_take: CubeFn<White>,
+    black_double: CubeFn<Black>,
+    black_take: CubeFn<Black>,
+}
+
 pub struct GamePlay<
     'a,
     R: Rng,
@@ -121,6 +314,34 @@ pub struct GamePlay<
     white: &'a mut White,
     generator: PhantomData<Generator>,
     black: Option<&'a mut Black>,
+    cube: Option<CubeAgents<White, Black>>,
+}
+
+impl<'a, R: Rng, White: MoveDecision, Black: MoveDecision, Generator: MoveGen>
+    GamePlay<'a, R, White, Black, Generator>
+{
+    fn doubles(&mut self, player: Bw) -> bool {
+        // without cube agents nobody doubles
+        let Some(cube) = &self.cube else {
+            return false;
+        };
+
+        match (&mut self.black, player) {
+            (Some(black), Bw::Black) => (cube.black_double)(&mut **black, player, self.game),
+            _ => (cube.white_double)(&mut *self.white, player, self.game),
+        }
+    }
+
+    fn takes(&mut self, player: Bw) -> bool {
+        let Some(cube) = &self.cube else {
+            return true;
+        };
+
+        match (&mut self.black, player) {
+            (Some(black), Bw::Black) => (cube.black_take)(&mut **black, player, self.game),
+            _ => (cube.white_take)(&mut *self.white, player, self.game),
+        }
+    }
 }
 
 impl<'a, R: Rng, White: MoveDecision, Black: MoveDecision, Generator: MoveGen> Iterator
@@ -131,6 +352,24 @@ impl<'a, R: Rng, White: MoveDecision, Black: MoveDecision, Generator: MoveGen> I
     fn next(&mut self) -> Option<Self::Item> {
         match self.game.state {
             GameState::Finished(_) => None,
+            GameState::BeforeRoll(player) => {
+                if self.doubles(player) {
+                    self.game.offer_double();
+                } else {
+                    self.game.roll(&mut self.rng);
+                }
+
+                Some(self.game.clone())
+            }
+            GameState::DoubleOffered(player) => {
+                if self.takes(-player) {
+                    self.game.take_cube(&mut self.rng);
+                } else {
+                    self.game.drop_cube();
+                }
+
+                Some(self.game.clone())
+            }
             GameState::Dice(player, _) => {
                 let moves = self.game.next_moves::<Generator>();
 
@@ -151,24 +390,65 @@ impl<'a, R: Rng, White: MoveDecision, Black: MoveDecision, Generator: MoveGen> I
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GameResult {
+    pub winner: Bw,
+    /// always `Single` when the cube was dropped
+    pub kind: WinKind,
+    /// cube value times the points of `kind`
+    pub points: u32,
+}
+
+impl GameResult {
+    #[inline]
+    pub fn new(winner: Bw, kind: WinKind, cube_value: u32) -> Self {
+        Self {
+            winner,
+            kind,
+            points: cube_value * kind.points(),
+        }
+    }
+}
+
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} won a {} ({})", self.winner, self.kind, self.points)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameState {
+    /// the player may double before rolling
+    BeforeRoll(Bw),
+    /// the player has doubled, the opponent has to take or drop
+    DoubleOffered(Bw),
     Dice(Bw, Dice),
-    Finished(Bw),
+    Finished(GameResult),
 }
 
 impl std::fmt::Display for GameState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            GameState::BeforeRoll(bw) => write!(f, "{} to roll", bw),
+            GameState::DoubleOffered(bw) => write!(f, "{} doubles", bw),
             GameState::Dice(bw, dice) => write!(f, "{}: {} {}", bw, dice.0 .0, dice.1 .0),
-            GameState::Finished(bw) => write!(f, "{} won", bw),
+            GameState::Finished(result) => write!(f, "{}", result),
         }
     }
 }
 
 impl std::fmt::Display for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}\n{}", self.board, self.state)
+        write!(f, "{:?}\n{}", self.board, self.state)?;
+
+        if let Some(cube) = self.cube {
+            match cube.owner {
+                Some(owner) => write!(f, " (cube {}, {})", cube.value, owner)?,
+                None => write!(f, " (cube {})", cube.value)?,
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -178,9 +458,14 @@ mod test {
 
     use crate::{
         decision::RandomMoveDecision,
-        game::{Game, GameState},
+        error::BgError,
+        game::{Cube, Game, GameResult, GameState},
         movegen::{simd::Simd1MoveGenerator, MoveGen},
-        types::prim::Bw,
+        types::{
+            board::{Board, BoardCoord, BoardError},
+            dice::{Dice, Die},
+            prim::{BOrW, Bw, WinKind},
+        },
     };
 
     #[test]
@@ -221,6 +506,7 @@ mod test {
     fn random_games_stats() {
         let mut blacks = 0;
         let mut whites = 0;
+        let mut gammons = 0;
 
         for _ in 0..100 {
             let mut game = Game::new(&mut rand::thread_rng());
@@ -235,18 +521,121 @@ mod test {
                 .count()
             );
 
-            match game.state {
-                GameState::Dice(_, _) => {}
-                GameState::Finished(Bw::Black) => {
+            let result = game.result().unwrap();
+
+            match result.winner {
+                Bw::Black => {
                     blacks += 1;
                 }
-                GameState::Finished(Bw::White) => {
+                Bw::White => {
                     whites += 1;
                 }
             }
+
+            if result.kind >= WinKind::Gammon {
+                gammons += 1;
+            }
+        }
+
+        println!("{} / {}, {} gammons", whites, blacks, gammons);
+    }
+
+    #[test]
+    fn test_cube_games() {
+        for _ in 0..100 {
+            let mut game = Game::with_cube(&mut rand::thread_rng());
+
+            let mut last_cube = 1;
+            for game in game.play_cube(
+                rand::thread_rng(),
+                &mut RandomMoveDecision(rand::thread_rng()),
+                &mut RandomMoveDecision(rand::thread_rng()),
+            ) {
+                let cube = game.cube.unwrap();
+
+                assert!(cube.value == last_cube || cube.value == last_cube * 2);
+                last_cube = cube.value;
+
+                if let GameState::DoubleOffered(player) = game.state {
+                    assert!(cube.owner != Some(-player));
+                }
+            }
+
+            let GameState::Finished(result) = game.state else {
+                panic!("game didn't finish");
+            };
+
+            assert_eq!(result.points, game.cube_value() * result.kind.points());
+
+            match game.board.winner() {
+                Some(winner) => {
+                    assert_eq!(winner, result.winner);
+                    assert_eq!(result.kind, game.board.win_kind(winner));
+                }
+                // dropped
+                None => assert_eq!(result.kind, WinKind::Single),
+            }
         }
+    }
+
+    #[test]
+    fn test_cube_actions() {
+        let rng = &mut rand::thread_rng();
+        let mut game = Game::with_cube(rng);
+        let GameState::Dice(player, _) = game.state else {
+            unreachable!()
+        };
 
-        println!("{} / {}", whites, blacks);
+        game.skip_move_by_necessity(rng);
+        assert!(matches!(game.state, GameState::BeforeRoll(p) if p == -player));
+
+        game.offer_double();
+        game.take_cube(rng);
+        assert_eq!(
+            game.cube,
+            Some(Cube {
+                value: 2,
+                owner: Some(player)
+            })
+        );
+        assert!(matches!(game.state, GameState::Dice(p, _) if p == -player));
+
+        // the owner may redouble, the other side rolls right away
+        game.skip_move_by_necessity(rng);
+        assert!(matches!(game.state, GameState::BeforeRoll(p) if p == player));
+        game.roll(rng);
+        game.skip_move_by_necessity(rng);
+        assert!(matches!(game.state, GameState::Dice(p, _) if p == -player));
+        game.skip_move_by_necessity(rng);
+
+        game.offer_double();
+        game.drop_cube();
+        assert_eq!(
+            game.result(),
+            Some(GameResult {
+                winner: player,
+                kind: WinKind::Single,
+                points: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_win_kind() {
+        // white has borne everything off
+        let mut board: Board = [(BoardCoord(20), BOrW::black(15))].into_iter().collect();
+        assert_eq!(board.win_kind(Bw::White), WinKind::Gammon);
+
+        board[BoardCoord(20)] = BOrW::black(14);
+        board[BoardCoord(3)] = BOrW::black(1);
+        assert_eq!(board.win_kind(Bw::White), WinKind::Backgammon);
+
+        board[BoardCoord(3)] = BOrW::empty();
+        board[BoardCoord::bar(Bw::Black)] = BOrW::black(1);
+        assert_eq!(board.win_kind(Bw::White), WinKind::Backgammon);
+
+        board[BoardCoord::bar(Bw::Black)] = BOrW::empty();
+        assert_eq!(board.win_kind(Bw::White), WinKind::Single);
     }
 
     #[test]
@@ -322,4 +711,78 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_serde() {
+        let mut game = Game::new(&mut rand::thread_rng());
+        game.play(
+            rand::thread_rng(),
+            &mut RandomMoveDecision(rand::thread_rng()),
+            &mut RandomMoveDecision(rand::thread_rng()),
+        )
+        .take(20)
+        .for_each(drop);
+        game.cube = Some(Cube {
+            value: 2,
+            owner: Some(Bw::Black),
+        });
+
+        let json = serde_json::to_string(&game).unwrap();
+        let read: Game = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(read.board, game.board);
+        assert_eq!(read.cube, game.cube);
+        assert_eq!(read.to_string(), game.to_string());
+    }
+
+    #[test]
+    fn test_checked() {
+        let rng = &mut rand::thread_rng();
+        let mut game = Game::new(rng);
+
+        let moves = game.try_next_moves::<Simd1MoveGenerator>().unwrap();
+        assert_eq!(moves, game.next_moves::<Simd1MoveGenerator>());
+
+        // some checker has to move from the start
+        assert_eq!(
+            game.make_move(rng, Board::default()),
+            Err(BgError::IllegalMove)
+        );
+        assert_eq!(game.skip_move(rng), Err(BgError::MustMove));
+        assert_eq!(game.make_move(rng, moves[0].clone()), Ok(()));
+        assert_eq!(game.board, moves[0]);
+
+        let mut broken = game.clone();
+        broken.board.0[30] = 1;
+        assert_eq!(
+            broken.try_next_moves::<Simd1MoveGenerator>(),
+            Err(BgError::InvalidBoard(BoardError::Padding(30)))
+        );
+
+        broken = game.clone();
+        broken.state = GameState::Dice(Bw::White, Dice(Die(9), Die(1)));
+        assert_eq!(
+            broken.try_next_moves::<Simd1MoveGenerator>(),
+            Err(BgError::InvalidDie(9))
+        );
+
+        // nor can a client send them
+        let json = r#"{"Dice":["White",[9,1]]}"#;
+        assert!(serde_json::from_str::<GameState>(json).is_err());
+        let json = r#"{"Dice":["White",[6,1]]}"#;
+        assert!(serde_json::from_str::<GameState>(json).is_ok());
+
+        game.state = GameState::BeforeRoll(Bw::White);
+        assert_eq!(game.skip_move(rng), Err(BgError::NotRolled));
+
+        game.state = GameState::Finished(GameResult::new(Bw::White, WinKind::Single, 1));
+        assert_eq!(
+            game.try_next_moves::<Simd1MoveGenerator>(),
+            Err(BgError::GameOver)
+        );
+        assert_eq!(
+            game.make_move(rng, Board::default()),
+            Err(BgError::GameOver)
+        );
+    }
 }
@@ -0,0 +1,531 @@
+//! Hypergammon solved exactly: every position reachable from a start, usually
+//! the 3-checker [`opening`], valued by value iteration.
+//!
+//! Values are kept for White on roll, Black's positions are looked up
+//! inverted. A side's checkers are indexed like bearoff home boards, just over
+//! 25 slots (the points and the bar) instead of 6, so each position has a
+//! fixed place in the table, reachable or not.
+//!
+//! Every record is 16 bytes: the five [`Probabilities`] as u16 `p * 65535`,
+//! then the money-game cubeful equity per unit of cube, as i16 `e * 10000`,
+//! with the cube in the middle, owned by the side on roll and owned by the
+//! opponent. The whole table for 3 checkers is about 170MB, and takes hours.
+
+use std::{fs, path::Path};
+
+use anyhow::{ensure, Context};
+
+use crate::{
+    bearoff::binomial,
+    decision::CubeDecision,
+    eval::{Evaluator, Probabilities},
+    game::Game,
+    movegen::{simd::Simd1MoveGenerator, MoveGen},
+    types::{
+        board::{Board, BoardCoord},
+        dice::Dice,
+        prim::{BOrW, Bw, WinKind},
+    },
+    util::table::TableFormat,
+};
+
+pub const MAGIC: &[u8; 8] = b"BGHYPER1";
+
+const FORMAT: TableFormat<1> = TableFormat {
+    magic: MAGIC,
+    name: "hypergammon solution",
+};
+
+const HEADER: usize = FORMAT.header_len();
+const RECORD: usize = 16;
+
+/// the points and the bar
+const SLOTS: u8 = 25;
+
+const PROBABILITY_SCALE: f32 = u16::MAX as f32;
+const EQUITY_SCALE: f32 = 10000.0;
+
+/// Each side has a checker on the opponent's 1, 2 and 3 points.
+pub fn opening() -> Board {
+    [
+        (BoardCoord(24), BOrW::white(1)),
+        (BoardCoord(23), BOrW::white(1)),
+        (BoardCoord(22), BOrW::white(1)),
+        (BoardCoord(1), BOrW::black(1)),
+        (BoardCoord(2), BOrW::black(1)),
+        (BoardCoord(3), BOrW::black(1)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverSettings {
+    /// stop once no value moves by more than this in a sweep
+    pub tolerance: f32,
+    pub max_sweeps: u32,
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-6,
+            max_sweeps: 1000,
+        }
+    }
+}
+
+/// where the cube is, seen from the side on roll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    Centered = 0,
+    Own = 1,
+    Opponent = 2,
+}
+
+impl Owner {
+    fn new(owner: Option<Bw>, player: Bw) -> Self {
+        match owner {
+            None => Owner::Centered,
+            Some(owner) if owner == player => Owner::Own,
+            Some(_) => Owner::Opponent,
+        }
+    }
+}
+
+/// what `color` has on each of their points and the bar, `None` with more
+/// than `checkers` of them
+fn side_index(board: &Board, color: Bw, checkers: u8) -> Option<usize> {
+    let mut total = 0;
+    let mut bar = 0;
+    let mut index = 0;
+
+    for slot in 1..=SLOTS {
+        let v = board[BoardCoord::rel(color, slot)];
+        let n = if v.matches(color) { v.to_count() } else { 0 };
+
+        total += n;
+        bar += n as usize + (slot > 1) as usize;
+        index += binomial(bar, slot as usize);
+    }
+
+    (total <= checkers).then_some(index)
+}
+
+fn sides(checkers: u8) -> usize {
+    binomial((SLOTS + checkers) as usize, checkers as usize)
+}
+
+/// the table index of `board` with White on roll
+fn state_index(board: &Board, checkers: u8) -> Option<usize> {
+    let white = side_index(board, Bw::White, checkers)?;
+    let black = side_index(board, Bw::Black, checkers)?;
+
+    Some(white * sides(checkers) + black)
+}
+
+pub struct Hypergammon<B = Vec<u8>> {
+    data: B,
+    checkers: u8,
+}
+
+impl Hypergammon {
+    /// Lists every position reachable from `start`, with either side on roll,
+    /// and iterates on their values until they settle.
+    pub fn solve(start: &Board, settings: SolverSettings) -> Self {
+        let checkers = [Bw::White, Bw::Black]
+            .into_iter()
+            .map(|color| {
+                (0..=SLOTS)
+                    .map(|i| start[BoardCoord(i)])
+                    .filter(|v| v.matches(color))
+                    .map(|v| v.to_count())
+                    .sum::<u8>()
+            })
+            .max()
+            .unwrap();
+
+        let states = sides(checkers).pow(2);
+        let index = |board: &Board| state_index(board, checkers).unwrap();
+
+        // breadth first, all with White on roll
+        let mut seen = vec![false; states];
+        let mut reachable = Vec::new();
+
+        for board in [start.clone(), start.inverse()] {
+            if board.winner().is_none() && !seen[index(&board)] {
+                seen[index(&board)] = true;
+                reachable.push(board);
+            }
+        }
+
+        let mut next = 0;
+        while next < reachable.len() {
+            let board = reachable[next].clone();
+            next += 1;
+
+            for (dice, _) in Dice::iter_unique() {
+                for m in moves(&board, dice) {
+                    if m.winner().is_some() {
+                        continue;
+                    }
+
+                    let after = m.inverse();
+                    let i = index(&after);
+
+                    if !seen[i] {
+                        seen[i] = true;
+                        reachable.push(after);
+                    }
+                }
+            }
+        }
+
+        let indexes: Vec<usize> = reachable.iter().map(index).collect();
+        let mut probabilities = vec![Probabilities::default(); states];
+        let mut cubeful = vec![[0f32; 3]; states];
+
+        for _ in 0..settings.max_sweeps {
+            let mut change = 0f32;
+
+            for (board, &i) in reachable.iter().zip(&indexes) {
+                let (p, c) = backup(board, checkers, |after| {
+                    let j = index(after);
+                    (probabilities[j], cubeful[j])
+                });
+
+                change = change.max((p.equity() - probabilities[i].equity()).abs());
+                for (new, old) in c.iter().zip(cubeful[i]) {
+                    change = change.max((new - old).abs());
+                }
+
+                probabilities[i] = p;
+                cubeful[i] = c;
+            }
+
+            if change < settings.tolerance {
+                break;
+            }
+        }
+
+        let mut data = FORMAT.start([checkers as u32], states * RECORD);
+
+        for (p, c) in probabilities.iter().zip(&cubeful) {
+            for v in [
+                p.win,
+                p.win_gammon,
+                p.win_backgammon,
+                p.lose_gammon,
+                p.lose_backgammon,
+            ] {
+                data.extend_from_slice(&((v * PROBABILITY_SCALE).round() as u16).to_le_bytes());
+            }
+            for e in c {
+                data.extend_from_slice(&((e * EQUITY_SCALE).round() as i16).to_le_bytes());
+            }
+        }
+
+        Self { data, checkers }
+    }
+
+    /// reads a whole solution file into memory
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+}
+
+/// White's chances on roll and cubeful equities, from `values` of the
+/// positions one roll away, with the opponent on roll and turned to White
+fn backup(
+    board: &Board,
+    checkers: u8,
+    values: impl Fn(&Board) -> (Probabilities, [f32; 3]),
+) -> (Probabilities, [f32; 3]) {
+    let mut p = Probabilities::default();
+    let mut no_double = [0f32; 3];
+
+    for (dice, weight) in Dice::iter_unique() {
+        let mut best = (Probabilities::default(), f32::MIN);
+        let mut best_cubeful = [f32::MIN; 3];
+
+        for m in moves(board, dice) {
+            let (after, after_cubeful) = match finished(&m, checkers) {
+                Some(p) => (p, [p.equity(); 3]),
+                None => {
+                    let (p, c) = values(&m.inverse());
+                    // the opponent's cube is the other way around
+                    (p.invert(), [-c[0], -c[2], -c[1]])
+                }
+            };
+
+            if after.equity() > best.1 {
+                best = (after, after.equity());
+            }
+            for (best, after) in best_cubeful.iter_mut().zip(after_cubeful) {
+                *best = best.max(after);
+            }
+        }
+
+        let weight = weight as f32 / 36.0;
+        p += best.0 * weight;
+        for (nd, best) in no_double.iter_mut().zip(best_cubeful) {
+            *nd += best * weight;
+        }
+    }
+
+    // a double is taken unless passing costs less, and turns the cube over to
+    // the opponent
+    let double = (2.0 * no_double[Owner::Opponent as usize]).min(1.0);
+    let cubeful = [
+        no_double[Owner::Centered as usize].max(double),
+        no_double[Owner::Own as usize].max(double),
+        no_double[Owner::Opponent as usize],
+    ];
+
+    (p, cubeful)
+}
+
+/// What the game is worth to White once they've borne off. A gammon is
+/// losing without bearing off any of the `checkers`, not 15 like
+/// [`Board::win_kind`] thinks.
+fn finished(board: &Board, checkers: u8) -> Option<Probabilities> {
+    if !board.is_winner(Bw::White) {
+        return None;
+    }
+
+    let left: u8 = (0..=SLOTS)
+        .map(|i| board[BoardCoord(i)])
+        .filter(|v| v.matches(Bw::Black))
+        .map(|v| v.to_count())
+        .sum();
+
+    let kind = if left < checkers {
+        WinKind::Single
+    } else if (0..=6).any(|i| board[BoardCoord(i)].matches(Bw::Black)) {
+        // on the bar or still in White's home
+        WinKind::Backgammon
+    } else {
+        WinKind::Gammon
+    };
+
+    Some(Probabilities {
+        win: 1.0,
+        win_gammon: (kind >= WinKind::Gammon) as u8 as f32,
+        win_backgammon: (kind >= WinKind::Backgammon) as u8 as f32,
+        lose_gammon: 0.0,
+        lose_backgammon: 0.0,
+    })
+}
+
+/// White's moves, or staying put when there are none
+fn moves(board: &Board, dice: Dice) -> Vec<Board> {
+    let moves = Simd1MoveGenerator::gen_unique_moves(board, dice, Bw::White);
+
+    if moves.is_empty() {
+        vec![board.clone()]
+    } else {
+        moves
+    }
+}
+
+impl<B: AsRef<[u8]>> Hypergammon<B> {
+    pub fn from_bytes(data: B) -> anyhow::Result<Self> {
+        let [checkers] = FORMAT.read(data.as_ref(), |[checkers]| {
+            ensure!(checkers <= 15, "{checkers} checkers is no hypergammon");
+
+            // a few more checkers than can be solved are already more than
+            // the address space
+            sides(checkers as u8)
+                .checked_pow(2)
+                .and_then(|states| states.checked_mul(RECORD))
+                .with_context(|| format!("{checkers} checkers don't fit in memory"))
+        })?;
+
+        Ok(Self {
+            data,
+            checkers: checkers as u8,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        Ok(fs::write(path, self.as_bytes())?)
+    }
+
+    /// checkers a side started with
+    pub fn checkers(&self) -> u8 {
+        self.checkers
+    }
+
+    fn record(&self, board: &Board, player: Bw) -> Option<&[u8]> {
+        let i = state_index(board.canonical(player).board(), self.checkers)?;
+
+        Some(&self.as_bytes()[HEADER + i * RECORD..HEADER + (i + 1) * RECORD])
+    }
+
+    /// Cubeless chances of `player`, who's on roll. `None` with too many
+    /// checkers; positions the solver never reached come out as all zeros.
+    pub fn probabilities(&self, board: &Board, player: Bw) -> Option<Probabilities> {
+        let record = self.record(board, player)?;
+        let p = |i: usize| u16::from_le_bytes([record[2 * i], record[2 * i + 1]]) as f32;
+
+        Some(Probabilities {
+            win: p(0) / PROBABILITY_SCALE,
+            win_gammon: p(1) / PROBABILITY_SCALE,
+            win_backgammon: p(2) / PROBABILITY_SCALE,
+            lose_gammon: p(3) / PROBABILITY_SCALE,
+            lose_backgammon: p(4) / PROBABILITY_SCALE,
+        })
+    }
+
+    /// Money-game equity of `player`, who's on roll, per unit of cube, with
+    /// the cube owned by `owner` or in the middle.
+    pub fn cubeful_equity(&self, board: &Board, player: Bw, owner: Option<Bw>) -> Option<f32> {
+        let record = self.record(board, player)?;
+        let at = 10 + 2 * Owner::new(owner, player) as usize;
+
+        Some(i16::from_le_bytes([record[at], record[at + 1]]) as f32 / EQUITY_SCALE)
+    }
+}
+
+impl<B: AsRef<[u8]>> Evaluator for Hypergammon<B> {
+    /// # Panics
+    /// With more checkers than the solution has.
+    fn evaluate(&self, board: &Board, player: Bw) -> Probabilities {
+        self.probabilities(board, player)
+            .expect("not a position of this hypergammon")
+    }
+}
+
+/// Perfect cube play for money, as far as the stored equities' rounding lets
+/// it tell.
+impl<B: AsRef<[u8]>> CubeDecision for Hypergammon<B> {
+    fn double(&mut self, player: Bw, game: &Game) -> bool {
+        let Some(cube) = game.cube else {
+            return false;
+        };
+
+        let equity = |owner| {
+            self.cubeful_equity(&game.board, player, owner)
+                .expect("not a position of this hypergammon")
+        };
+
+        // the cubeful value already has the best of doubling and not
+        // doubling in it, so doubling is right when it gets all of it. Both
+        // are rounded to `1 / EQUITY_SCALE` on their own, and twice one of
+        // them can come out a step short of the other.
+        let doubled = (2.0 * equity(Some(-player))).min(1.0);
+        cube.owner != Some(-player) && doubled >= equity(cube.owner) - 2.0 / EQUITY_SCALE
+    }
+
+    fn take(&mut self, player: Bw, game: &Game) -> bool {
+        let doubler = -player;
+
+        let equity = self
+            .cubeful_equity(&game.board, doubler, Some(player))
+            .expect("not a position of this hypergammon");
+
+        2.0 * equity <= 1.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decision::CubeDecision,
+        eval::Evaluator,
+        game::{Cube, Game, GameState},
+        types::{
+            board::{Board, BoardCoord},
+            prim::{BOrW, Bw},
+        },
+    };
+
+    use super::{backup, opening, Hypergammon, SolverSettings};
+
+    /// a checker each, about to pass each other
+    fn start() -> Board {
+        [
+            (BoardCoord(14), BOrW::white(1)),
+            (BoardCoord(11), BOrW::black(1)),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_opening() {
+        let board = opening();
+
+        assert_eq!(board.pip_count(Bw::White), 69);
+        assert_eq!(board.pip_count(Bw::Black), 69);
+        assert_eq!(board.inverse(), board);
+    }
+
+    #[test]
+    fn test_solve() {
+        let solved = Hypergammon::solve(&start(), SolverSettings::default());
+        assert_eq!(solved.checkers(), 1);
+
+        // the values are their own one roll lookahead
+        let board = start();
+        let (p, cubeful) = backup(&board, 1, |after| {
+            let p = solved.probabilities(after, Bw::White).unwrap();
+            let c = [None, Some(Bw::White), Some(Bw::Black)]
+                .map(|owner| solved.cubeful_equity(after, Bw::White, owner).unwrap());
+            (p, c)
+        });
+        assert!((p.equity() - solved.evaluate(&board, Bw::White).equity()).abs() < 1e-3);
+        assert!(
+            (cubeful[0] - solved.cubeful_equity(&board, Bw::White, None).unwrap()).abs() < 1e-3
+        );
+
+        let equity = |owner| solved.cubeful_equity(&board, Bw::White, owner).unwrap();
+        assert!(equity(Some(Bw::White)) >= equity(None));
+        assert!(equity(None) >= equity(Some(Bw::Black)));
+
+        let read = Hypergammon::from_bytes(solved.as_bytes()).unwrap();
+        assert_eq!(
+            read.probabilities(&board, Bw::Black),
+            solved.probabilities(&board, Bw::Black)
+        );
+        assert_eq!(read.probabilities(&opening(), Bw::White), None);
+
+        // too many states to even count, not a panic
+        for checkers in [12u32, 15] {
+            let mut header = solved.as_bytes()[..12].to_vec();
+            header[8..12].copy_from_slice(&checkers.to_le_bytes());
+            assert!(Hypergammon::from_bytes(header).is_err());
+        }
+    }
+
+    #[test]
+    fn test_gammons() {
+        // White gets off with any roll, and Black hasn't borne off yet
+        let last: Board = [
+            (BoardCoord(2), BOrW::white(1)),
+            (BoardCoord(11), BOrW::black(1)),
+        ]
+        .into_iter()
+        .collect();
+        let solved = Hypergammon::solve(&last, SolverSettings::default());
+
+        assert_eq!(solved.evaluate(&last, Bw::White).win_gammon, 1.0);
+        assert_eq!(solved.evaluate(&last, Bw::White).equity(), 2.0);
+        // too good to double
+        assert_eq!(solved.cubeful_equity(&last, Bw::White, None), Some(2.0));
+
+        let mut solved = solved;
+        let game = Game {
+            board: last,
+            state: GameState::BeforeRoll(Bw::White),
+            cube: Some(Cube::default()),
+            match_score: None,
+        };
+        assert!(!solved.double(Bw::White, &game));
+        assert!(!solved.take(Bw::Black, &game));
+    }
+}
@@ -1,20 +1,34 @@
 #![feature(portable_simd)]
 
+pub mod bearoff;
 pub mod decision;
+pub mod encode;
+pub mod error;
+pub mod eval;
+pub mod formats;
 pub mod game;
+pub mod hypergammon;
+pub mod matchplay;
 pub mod movegen;
+pub mod notation;
+pub mod race;
 pub mod randgen;
+pub mod rollout;
+pub mod search;
 pub mod types;
 pub mod util;
+pub mod zobrist;
 
 #[cfg(feature = "backgammon-compat")]
 pub mod compat;
 
 pub use {
-    game::Game, game::GameState, movegen::basic::BasicMoveGenerator,
-    movegen::simd::Simd1MoveGenerator, movegen::MoveGen, types::board::Board,
-    types::board::BoardCoord, types::dice::Dice, types::dice::Die, types::prim::BAndW,
-    types::prim::BOrW, types::prim::Bw,
+    encode::batch::BoardBatch, error::BgError, game::Game, game::GameResult, game::GameState,
+    movegen::basic::BasicMoveGenerator, movegen::simd::Simd1MoveGenerator, movegen::MoveGen,
+    types::board::Board, types::board::BoardCoord, types::canonical::CanonicalBoard,
+    types::class::PositionClass, types::dice::Dice, types::dice::Die, types::play::Play,
+    types::play::Step, types::prim::BAndW, types::prim::BOrW, types::prim::Bw,
+    types::prim::WinKind,
 };
 
 #[cfg(test)]
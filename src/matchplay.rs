@@ -0,0 +1,288 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decision::{CubeDecision, MoveDecision},
+    game::{Cube, Game, GameResult},
+    types::prim::{BAndW, Bw},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// where a game stands in its match, for the decision makers to see
+pub struct MatchScore {
+    pub length: u8,
+    pub score: BAndW,
+    /// no doubling in the Crawford game
+    pub crawford: bool,
+}
+
+impl MatchScore {
+    /// points `player` still needs to win the match
+    #[inline]
+    pub fn away(&self, player: Bw) -> u8 {
+        self.length.saturating_sub(self.score.get(&player))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    pub winner: Bw,
+    pub score: BAndW,
+    /// every game, in order
+    pub games: Vec<GameResult>,
+}
+
+#[derive(Debug, Clone)]
+/// successive games until someone has `length` points
+pub struct Match {
+    pub length: u8,
+    pub score: BAndW,
+    pub game: Game,
+    /// every finished game
+    pub games: Vec<GameResult>,
+    crawford_played: bool,
+}
+
+impl Match {
+    pub fn new(rng: &mut impl Rng, length: u8) -> Self {
+        assert!(length > 0, "a match is at least one point long");
+
+        let score = BAndW::empty();
+
+        Self {
+            length,
+            score,
+            game: Self::new_game(rng, length, score, false),
+            games: vec![],
+            crawford_played: false,
+        }
+    }
+
+    fn new_game(rng: &mut impl Rng, length: u8, score: BAndW, crawford: bool) -> Game {
+        Game {
+            cube: if crawford {
+                None
+            } else {
+                Some(Cube::default())
+            },
+            match_score: Some(MatchScore {
+                length,
+                score,
+                crawford,
+            }),
+            ..Game::new(rng)
+        }
+    }
+
+    #[inline]
+    pub fn winner(&self) -> Option<Bw> {
+        [Bw::White, Bw::Black]
+            .into_iter()
+            .find(|bw| self.score.get(bw) >= self.length)
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    /// Scores the finished game and, unless the match is over, starts the next one.
+    ///
+    /// The first game after someone gets to one point away is the Crawford game.
+    pub fn next_game(&mut self, rng: &mut impl Rng) {
+        let Some(result) = self.game.result() else {
+            panic!("the game is not finished yet");
+        };
+
+        self.games.push(result);
+
+        let score = (self.score.get(&result.winner) as u32 + result.points).min(u8::MAX as u32);
+        self.score.set(&result.winner, score as u8);
+
+        if self.is_finished() {
+            return;
+        }
+
+        let crawford = !self.crawford_played
+            && [Bw::White, Bw::Black]
+                .iter()
+                .any(|bw| self.score.get(bw) == self.length - 1);
+
+        self.crawford_played |= crawford;
+        self.game = Self::new_game(rng, self.length, self.score, crawford);
+    }
+
+    pub fn result(&self) -> Option<MatchResult> {
+        Some(MatchResult {
+            winner: self.winner()?,
+            score: self.score,
+            games: self.games.clone(),
+        })
+    }
+
+    pub fn play<
+        'a,
+        R: Rng,
+        White: MoveDecision + CubeDecision,
+        Black: MoveDecision + CubeDecision,
+    >(
+        &'a mut self,
+        rng: R,
+        white: &'a mut White,
+        black: &'a mut Black,
+    ) -> MatchPlay<'a, R, White, Black> {
+        MatchPlay {
+            m: self,
+            rng,
+            white,
+            black: Some(black),
+        }
+    }
+
+    pub fn play_self<'a, R: Rng, White: MoveDecision + CubeDecision>(
+        &'a mut self,
+        rng: R,
+        white: &'a mut White,
+    ) -> MatchPlay<'a, R, White> {
+        MatchPlay {
+            m: self,
+            rng,
+            white,
+            black: None,
+        }
+    }
+}
+
+/// Like `GamePlay`, going through every game of the match.
+pub struct MatchPlay<
+    'a,
+    R: Rng,
+    White: MoveDecision + CubeDecision,
+    Black: MoveDecision + CubeDecision = White,
+> {
+    m: &'a mut Match,
+    rng: R,
+    white: &'a mut White,
+    black: Option<&'a mut Black>,
+}
+
+impl<'a, R: Rng, White: MoveDecision + CubeDecision, Black: MoveDecision + CubeDecision> Iterator
+    for MatchPlay<'a, R, White, Black>
+{
+    type Item = Game;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.m.is_finished() {
+            return None;
+        }
+
+        if self.m.game.is_finished() {
+            self.m.next_game(&mut self.rng);
+
+            if self.m.is_finished() {
+                return None;
+            }
+
+            return Some(self.m.game.clone());
+        }
+
+        match &mut self.black {
+            Some(black) => self
+                .m
+                .game
+                .play_cube(&mut self.rng, &mut *self.white, &mut **black)
+                .next(),
+            None => self
+                .m
+                .game
+                .play_self_cube(&mut self.rng, &mut *self.white)
+                .next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decision::RandomMoveDecision,
+        game::{GameResult, GameState},
+        types::prim::{Bw, WinKind},
+    };
+
+    use super::Match;
+
+    #[test]
+    fn test_random_matches() {
+        for length in [1, 3, 7] {
+            for _ in 0..20 {
+                let mut m = Match::new(&mut rand::thread_rng(), length);
+
+                let mut crawford_games = 0;
+                let mut last_crawford = false;
+
+                for game in m.play(
+                    rand::thread_rng(),
+                    &mut RandomMoveDecision(rand::thread_rng()),
+                    &mut RandomMoveDecision(rand::thread_rng()),
+                ) {
+                    let score = game.match_score.unwrap();
+
+                    if score.crawford {
+                        assert!(game.cube.is_none());
+                        assert!(!matches!(game.state, GameState::DoubleOffered(_)));
+
+                        if !last_crawford {
+                            crawford_games += 1;
+                        }
+                    }
+                    last_crawford = score.crawford;
+                }
+
+                let result = m.result().unwrap();
+
+                assert!(crawford_games <= 1);
+                assert!(result.score.get(&result.winner) >= length);
+                assert!(result.score.get(&-result.winner) < length);
+
+                for bw in [Bw::White, Bw::Black] {
+                    let points: u32 = result
+                        .games
+                        .iter()
+                        .filter(|game| game.winner == bw)
+                        .map(|game| game.points)
+                        .sum();
+
+                    // random players double like there's no tomorrow
+                    assert_eq!(result.score.get(&bw) as u32, points.min(u8::MAX as u32));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_crawford() {
+        let rng = &mut rand::thread_rng();
+        let mut m = Match::new(rng, 5);
+
+        m.game.state = GameState::Finished(GameResult::new(Bw::White, WinKind::Gammon, 2));
+        m.next_game(rng);
+
+        assert!(m.game.match_score.unwrap().crawford);
+        assert!(m.game.cube.is_none());
+
+        m.game.state = GameState::Finished(GameResult::new(Bw::Black, WinKind::Single, 2));
+        m.next_game(rng);
+
+        // post-Crawford
+        assert!(!m.game.match_score.unwrap().crawford);
+        assert!(m.game.cube.is_some());
+        assert_eq!(m.game.match_score.unwrap().away(Bw::White), 1);
+        assert_eq!(m.game.match_score.unwrap().away(Bw::Black), 3);
+
+        m.game.state = GameState::Finished(GameResult::new(Bw::Black, WinKind::Backgammon, 1));
+        m.next_game(rng);
+
+        assert_eq!(m.winner(), Some(Bw::Black));
+        assert_eq!(m.result().unwrap().games.len(), 3);
+    }
+}
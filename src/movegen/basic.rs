@@ -19,6 +19,17 @@ fn any_not_home(player: Bw, board: &Board) -> bool {
     false
 }
 
+/// any of `player`'s checkers on home points above `point` (counted from their side)
+fn any_higher_home(player: Bw, board: &Board, point: u8) -> bool {
+    for i in point + 1..=6 {
+        if board[BoardCoord::rel(player, i)].matches(player) {
+            return true;
+        }
+    }
+
+    false
+}
+
 impl MoveGen for BasicMoveGenerator {
     fn gen_moves_one_die(
         board: &crate::types::board::Board,
@@ -72,7 +83,9 @@ impl MoveGen for BasicMoveGenerator {
                     continue;
                 }
 
-                if coord.perspective(player) <= die.0 {
+                let point = coord.perspective(player);
+
+                if point == die.0 || (point < die.0 && !any_higher_home(player, board, point)) {
                     let mut b = board.clone();
 
                     b[coord] -= 1;
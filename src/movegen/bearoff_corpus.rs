@@ -0,0 +1,163 @@
+//! Bear-off regression positions both generators have to agree on.
+//!
+//! Positions are written from White's side (points 1..=6 are White's home),
+//! and every case is also checked mirrored for Black via `Board::inverse`.
+
+use crate::{
+    movegen::{basic::BasicMoveGenerator, simd::Simd1MoveGenerator, MoveGen},
+    types::{
+        board::{Board, BoardCoord},
+        dice::{Dice, Die},
+        prim::{BOrW, Bw},
+    },
+};
+
+/// White's checkers (point, count), with the white bar at 25
+type Whites = &'static [(u8, u8)];
+
+struct OneDieCase {
+    name: &'static str,
+    whites: Whites,
+    die: u8,
+    expected: &'static [Whites],
+}
+
+struct DiceCase {
+    name: &'static str,
+    whites: Whites,
+    dice: (u8, u8),
+    expected: &'static [Whites],
+}
+
+const ONE_DIE: &[OneDieCase] = &[
+    OneDieCase {
+        name: "exact pip",
+        whites: &[(6, 1), (3, 1)],
+        die: 6,
+        expected: &[&[(3, 1)]],
+    },
+    OneDieCase {
+        name: "higher die from the highest point",
+        whites: &[(5, 1), (3, 1)],
+        die: 6,
+        expected: &[&[(3, 1)]],
+    },
+    OneDieCase {
+        name: "higher die with a single point",
+        whites: &[(2, 2)],
+        die: 5,
+        expected: &[&[(2, 1)]],
+    },
+    OneDieCase {
+        name: "no higher-die removal below an occupied point",
+        whites: &[(5, 1), (3, 1)],
+        die: 4,
+        expected: &[&[(1, 1), (3, 1)]],
+    },
+    OneDieCase {
+        name: "exact pip with a checker above",
+        whites: &[(6, 1), (4, 1)],
+        die: 4,
+        expected: &[&[(6, 1)], &[(4, 1), (2, 1)]],
+    },
+    OneDieCase {
+        name: "small die moves inside",
+        whites: &[(6, 1)],
+        die: 1,
+        expected: &[&[(5, 1)]],
+    },
+    OneDieCase {
+        name: "no removal with a checker outside home",
+        whites: &[(7, 1), (2, 1)],
+        die: 2,
+        expected: &[&[(5, 1), (2, 1)]],
+    },
+    OneDieCase {
+        name: "no removal with a checker on the bar",
+        whites: &[(25, 1), (3, 14)],
+        die: 3,
+        expected: &[&[(22, 1), (3, 14)]],
+    },
+];
+
+const DICE: &[DiceCase] = &[
+    DiceCase {
+        name: "both off",
+        whites: &[(6, 1), (5, 1)],
+        dice: (6, 5),
+        expected: &[&[], &[(1, 1)]],
+    },
+    DiceCase {
+        name: "move in, then bear off",
+        whites: &[(8, 1), (3, 1)],
+        dice: (5, 3),
+        expected: &[&[(3, 1)]],
+    },
+    DiceCase {
+        name: "double bears off from the top",
+        whites: &[(4, 1), (3, 1), (2, 1)],
+        dice: (5, 5),
+        expected: &[&[]],
+    },
+];
+
+/// Black sits out of the way, in its own home board
+fn board(whites: Whites) -> Board {
+    whites
+        .iter()
+        .map(|(point, n)| (BoardCoord(*point), BOrW::white(*n)))
+        .chain([(BoardCoord(20), BOrW::black(15))])
+        .collect()
+}
+
+fn check(name: &str, board: &Board, player: Bw, mut moves: Vec<Board>, expected: &[Whites]) {
+    let mut expected = expected
+        .iter()
+        .map(|whites| self::board(whites))
+        .map(|b| if player == Bw::Black { b.inverse() } else { b })
+        .collect::<Vec<_>>();
+
+    moves.sort();
+    expected.sort();
+
+    assert_eq!(moves, expected, "{} ({}) on\n{:?}", name, player, board);
+}
+
+fn check_generator<Generator: MoveGen>() {
+    for case in ONE_DIE {
+        for player in [Bw::White, Bw::Black] {
+            let mut b = board(case.whites);
+            if player == Bw::Black {
+                b = b.inverse();
+            }
+
+            let moves = Generator::gen_unique_moves_one_die(&b, Die(case.die), player);
+
+            check(case.name, &b, player, moves, case.expected);
+        }
+    }
+
+    for case in DICE {
+        for player in [Bw::White, Bw::Black] {
+            let mut b = board(case.whites);
+            if player == Bw::Black {
+                b = b.inverse();
+            }
+
+            let dice = Dice(Die(case.dice.0), Die(case.dice.1));
+            let moves = Generator::gen_unique_moves(&b, dice, player);
+
+            check(case.name, &b, player, moves, case.expected);
+        }
+    }
+}
+
+#[test]
+fn test_bearoff_basic() {
+    check_generator::<BasicMoveGenerator>();
+}
+
+#[test]
+fn test_bearoff_simd() {
+    check_generator::<Simd1MoveGenerator>();
+}
@@ -4,6 +4,7 @@ use crate::{
     types::{
         board::Board,
         dice::{Dice, Die},
+        play::{Play, Step},
         prim::Bw,
     },
     util::timings,
@@ -12,10 +13,25 @@ use crate::{
 pub mod basic;
 pub mod simd;
 
+#[cfg(test)]
+mod bearoff_corpus;
+
+/// Generators take boards on trust; debug builds make sure they're valid.
+#[inline]
+fn debug_validate(board: &Board) {
+    if cfg!(debug_assertions) {
+        if let Err(err) = board.validate() {
+            panic!("invalid board, {}:\n{:?}", err, board);
+        }
+    }
+}
+
 pub trait MoveGen {
     fn gen_moves_one_die(board: &Board, die: Die, player: Bw) -> Vec<Board>;
 
     fn gen_unique_moves_one_die(board: &Board, die: Die, player: Bw) -> Vec<Board> {
+        debug_validate(board);
+
         timings::time(timings::PerfParts::UniqueOneDie, || {
             Self::gen_moves_one_die(board, die, player)
                 .into_iter()
@@ -24,13 +40,21 @@ pub trait MoveGen {
         })
     }
 
+    /// All the distinct boards reachable by a legal play of `dice`.
+    ///
+    /// Only plays using as many dice as possible are kept; if just one die
+    /// of a non-double can be played, it has to be the larger one when that's
+    /// possible. An empty result means the player can't move at all.
     fn gen_unique_moves(board: &Board, dice: Dice, player: Bw) -> Vec<Board> {
+        debug_validate(board);
+
         timings::time(timings::PerfParts::UniqueMoves, || {
             if dice.is_double() {
                 return timings::time(timings::PerfParts::UniqueMovesDouble, || {
                     let die = dice.0;
 
                     let mut boards = vec![board.clone()];
+                    let mut used = 0;
 
                     for _ in 0..4 {
                         let new_boards = boards
@@ -43,9 +67,14 @@ pub trait MoveGen {
                             break;
                         } else {
                             boards = new_boards;
+                            used += 1;
                         }
                     }
 
+                    if used == 0 {
+                        return vec![];
+                    }
+
                     boards
                 });
             }
@@ -55,16 +84,93 @@ pub trait MoveGen {
                 let d01 = d0
                     .iter()
                     .flat_map(|b| Self::gen_moves_one_die(&b, dice.1, player));
-                let d1 = Self::gen_moves_one_die(board, dice.0, player);
+                let d1 = Self::gen_moves_one_die(board, dice.1, player);
                 let d10 = d1
                     .iter()
-                    .flat_map(|b| Self::gen_moves_one_die(&b, dice.1, player));
+                    .flat_map(|b| Self::gen_moves_one_die(&b, dice.0, player));
 
-                d01.chain(d10).unique().collect()
+                let both = d01.chain(d10).unique().collect::<Vec<_>>();
+
+                if !both.is_empty() {
+                    return both;
+                }
+
+                // only one die can be played: the larger one if possible
+                let (larger, smaller) = if dice.0 >= dice.1 { (d0, d1) } else { (d1, d0) };
+
+                if !larger.is_empty() {
+                    larger.into_iter().unique().collect()
+                } else {
+                    smaller.into_iter().unique().collect()
+                }
             })
         })
     }
 
+    /// Like `gen_moves_one_die`, along with the step that leads to each board.
+    fn gen_steps_one_die(board: &Board, die: Die, player: Bw) -> Vec<(Step, Board)> {
+        Self::gen_moves_one_die(board, die, player)
+            .into_iter()
+            .map(|b| (Step::between(board, &b, player, die), b))
+            .collect()
+    }
+
+    /// Same boards as `gen_unique_moves`, each with one of the plays reaching it.
+    fn gen_unique_plays(board: &Board, dice: Dice, player: Bw) -> Vec<(Play, Board)> {
+        debug_validate(board);
+
+        let orders = if dice.is_double() {
+            vec![dice.dice()]
+        } else {
+            vec![vec![dice.0, dice.1], vec![dice.1, dice.0]]
+        };
+
+        let mut best: Vec<(Play, Board)> = vec![];
+        let mut best_len = 0;
+
+        for order in orders {
+            let mut plays = vec![(Play::new(player), board.clone())];
+
+            for die in order {
+                let new_plays = plays
+                    .iter()
+                    .flat_map(|(play, b)| {
+                        Self::gen_steps_one_die(b, die, player)
+                            .into_iter()
+                            .map(|(step, nb)| (play.with(step), nb))
+                    })
+                    .unique_by(|(_, b)| b.clone())
+                    .collect::<Vec<_>>();
+
+                if new_plays.is_empty() {
+                    break;
+                } else {
+                    plays = new_plays;
+                }
+            }
+
+            let len = plays[0].0.len();
+
+            if len > best_len {
+                best = plays;
+                best_len = len;
+            } else if len == best_len && len > 0 {
+                best.extend(plays);
+            }
+        }
+
+        // only one die of a non-double can be played: the larger one if possible
+        if best_len == 1 {
+            let larger = dice.0.max(dice.1);
+
+            if best.iter().any(|(play, _)| play.steps()[0].die == larger) {
+                best.retain(|(play, _)| play.steps()[0].die == larger);
+            }
+        }
+
+        best.into_iter().unique_by(|(_, b)| b.clone()).collect()
+    }
+
     // fn gen_all_possible_moves(&self, board: &Board, player: Bw) -> Vec<Vec<Board>> {
     //     let mut moves = Vec::new();
 
@@ -75,3 +181,175 @@ pub trait MoveGen {
     //     moves
     // }
 }
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::{
+        movegen::{basic::BasicMoveGenerator, simd::Simd1MoveGenerator, MoveGen},
+        types::{
+            board::{Board, BoardCoord},
+            dice::{Dice, Die},
+            play::Step,
+            prim::{BOrW, Bw},
+        },
+    };
+
+    fn both_generators(board: &Board, dice: Dice, player: Bw) -> Vec<Board> {
+        let mut simd = Simd1MoveGenerator::gen_unique_moves(board, dice, player);
+        let mut basic = BasicMoveGenerator::gen_unique_moves(board, dice, player);
+
+        simd.sort();
+        basic.sort();
+
+        assert_eq!(simd, basic);
+
+        simd
+    }
+
+    #[test]
+    fn test_larger_die_rule() {
+        // white can play either the 6 or the 5 from 13, but not both: 2 is blocked
+        let board: Board = [
+            (BoardCoord(13), BOrW::white(1)),
+            (BoardCoord(2), BOrW::black(2)),
+            (BoardCoord(20), BOrW::black(13)),
+        ]
+        .into_iter()
+        .collect();
+
+        let expected: Board = [
+            (BoardCoord(7), BOrW::white(1)),
+            (BoardCoord(2), BOrW::black(2)),
+            (BoardCoord(20), BOrW::black(13)),
+        ]
+        .into_iter()
+        .collect();
+
+        for dice in [Dice(Die(6), Die(5)), Dice(Die(5), Die(6))] {
+            assert_eq!(
+                both_generators(&board, dice, Bw::White),
+                vec![expected.clone()]
+            );
+        }
+    }
+
+    #[test]
+    fn test_smaller_die_when_larger_blocked() {
+        // the 6 from 13 is blocked, so the 5 is the only thing to play
+        let board: Board = [
+            (BoardCoord(13), BOrW::white(1)),
+            (BoardCoord(7), BOrW::black(2)),
+            (BoardCoord(2), BOrW::black(2)),
+            (BoardCoord(20), BOrW::black(11)),
+        ]
+        .into_iter()
+        .collect();
+
+        let moves = both_generators(&board, Dice(Die(6), Die(5)), Bw::White);
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0][BoardCoord(8)], BOrW::white(1));
+    }
+
+    #[test]
+    fn test_both_dice_in_either_order() {
+        // the 1 is blocked first, so only 2-then-1 uses both dice
+        let board: Board = [
+            (BoardCoord(10), BOrW::white(1)),
+            (BoardCoord(9), BOrW::black(2)),
+            (BoardCoord(20), BOrW::black(13)),
+        ]
+        .into_iter()
+        .collect();
+
+        let moves = both_generators(&board, Dice(Die(1), Die(2)), Bw::White);
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0][BoardCoord(7)], BOrW::white(1));
+    }
+
+    #[test]
+    fn test_plays_match_moves() {
+        let rng = &mut rand::thread_rng();
+
+        for iter in 0..1000 {
+            let board: Board = rng.gen();
+            let dice: Dice = rng.gen();
+            let player: Bw = rng.gen();
+
+            let mut moves = Simd1MoveGenerator::gen_unique_moves(&board, dice, player);
+            let plays = Simd1MoveGenerator::gen_unique_plays(&board, dice, player);
+
+            let mut play_boards = plays.iter().map(|(_, b)| b.clone()).collect::<Vec<_>>();
+
+            moves.sort();
+            play_boards.sort();
+
+            assert_eq!(moves, play_boards, "iter: {}", iter);
+
+            for (play, after) in plays {
+                let mut b = board.clone();
+
+                b.apply(&play);
+                assert_eq!(b, after, "iter: {}, {:?}", iter, play);
+
+                b.unapply(&play);
+                assert_eq!(b, board, "iter: {}, {:?}", iter, play);
+            }
+        }
+    }
+
+    #[test]
+    fn test_play_steps() {
+        // 13/7* with the 6, then 7/2 with the 5
+        let board: Board = [
+            (BoardCoord(13), BOrW::white(1)),
+            (BoardCoord(7), BOrW::black(1)),
+            (BoardCoord(20), BOrW::black(14)),
+        ]
+        .into_iter()
+        .collect();
+
+        let plays = BasicMoveGenerator::gen_unique_plays(&board, Dice(Die(6), Die(5)), Bw::White);
+        let (play, after) = plays
+            .iter()
+            .find(|(_, b)| b[BoardCoord::bar(Bw::Black)] == BOrW::black(1))
+            .unwrap();
+
+        assert_eq!(after[BoardCoord(2)], BOrW::white(1));
+        assert_eq!(play.hits(), 1);
+        assert_eq!(
+            play.steps(),
+            &[
+                Step {
+                    from: BoardCoord(13),
+                    to: BoardCoord(7),
+                    die: Die(6),
+                    hit: true
+                },
+                Step {
+                    from: BoardCoord(7),
+                    to: BoardCoord(2),
+                    die: Die(5),
+                    hit: false
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_moves_is_empty() {
+        let board: Board = [
+            (BoardCoord(25), BOrW::white(1)),
+            (BoardCoord(22), BOrW::black(2)),
+            (BoardCoord(20), BOrW::black(13)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(both_generators(&board, Dice(Die(3), Die(3)), Bw::White).is_empty());
+        assert!(both_generators(&board, Dice(Die(3), Die(5)), Bw::White).is_empty());
+    }
+}
@@ -122,25 +122,40 @@ impl MoveGen for Simd1MoveGenerator {
                 if !removals_possible {
                     vec![]
                 } else {
-                    // let range = match player {
-                    //     Bw::White => 1..=udie,
-                    //     Bw::Black => (18 - udie)..=18,
-                    // };
-
-                    (1..=udie as u8)
-                        .filter_map(|i| {
-                            let c = BoardCoord::rel(player, i);
-                            if board[c].matches(player) {
-                                let mut b = board.clone();
-
-                                b[c] -= 1;
-
-                                Some(b)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect()
+                    // bearing off with a higher die is only allowed from the highest point
+                    let mut higher_home = Simd::splat(0);
+                    for i in (udie + 1)..=6 {
+                        higher_home[BoardCoord::rel(player, i as u8).0 as usize] = 1;
+                    }
+                    let on_higher = simdboard * higher_home;
+
+                    let higher_occupied = match player {
+                        Bw::White => on_higher.is_positive().any(),
+                        Bw::Black => on_higher.is_negative().any(),
+                    };
+
+                    let exact = BoardCoord::rel(player, udie as u8);
+
+                    let from = if board[exact].matches(player) {
+                        Some(exact)
+                    } else if higher_occupied {
+                        None
+                    } else {
+                        (1..udie as u8)
+                            .rev()
+                            .map(|i| BoardCoord::rel(player, i))
+                            .find(|c| board[*c].matches(player))
+                    };
+
+                    from.map(|c| {
+                        let mut b = board.clone();
+
+                        b[c] -= 1;
+
+                        b
+                    })
+                    .into_iter()
+                    .collect()
                 }
             });
 
@@ -233,7 +248,11 @@ mod test {
 
     use crate::{
         movegen::{basic::BasicMoveGenerator, MoveGen},
-        types::{board::Board, dice::Die, prim::Bw},
+        types::{
+            board::Board,
+            dice::{Dice, Die},
+            prim::Bw,
+        },
         util::timings::PERF_MAP,
     };
 
@@ -264,4 +283,23 @@ mod test {
 
         println!("{:?}", *PERF_MAP);
     }
+
+    #[test]
+    pub fn test_same_random_unique_moves() {
+        let rng = &mut rand::thread_rng();
+
+        for iter in 0..1000 {
+            let board: Board = rng.gen();
+            let dice: Dice = rng.gen();
+            let player: Bw = rng.gen();
+
+            let mut basic_moves = BasicMoveGenerator::gen_unique_moves(&board, dice, player);
+            let mut simd_moves = Simd1MoveGenerator::gen_unique_moves(&board, dice, player);
+
+            basic_moves.sort();
+            simd_moves.sort();
+
+            assert_eq!(basic_moves, simd_moves, "iter: {}", iter);
+        }
+    }
 }
@@ -0,0 +1,461 @@
+//! Standard move notation, e.g. `24/18 13/11*`, `8/4(2)`, `bar/22`, `6/off`.
+//!
+//! Points are numbered from the moving player's side (`BoardCoord::perspective`),
+//! so the bar is 25 and borne-off checkers go to 0.
+
+use std::fmt::{Display, Formatter};
+
+use anyhow::{anyhow, bail};
+use itertools::Itertools;
+
+use crate::{
+    movegen::MoveGen,
+    types::{
+        board::{Board, BoardCoord},
+        dice::Dice,
+        play::Play,
+        prim::Bw,
+    },
+};
+
+const BAR: u8 = 25;
+const OFF: u8 = 0;
+
+/// one checker's path: starting point, then every point it lands on and whether it hit there
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Path {
+    from: u8,
+    to: Vec<(u8, bool)>,
+}
+
+impl Path {
+    fn end(&self) -> u8 {
+        self.to.last().unwrap().0
+    }
+
+    /// intermediate points are only kept where something was hit
+    fn simplified(mut self) -> Path {
+        let last = self.to.pop().unwrap();
+
+        self.to.retain(|(_, hit)| *hit);
+        self.to.push(last);
+
+        self
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", point_name(self.from))?;
+
+        for (point, hit) in &self.to {
+            write!(f, "/{}", point_name(*point))?;
+
+            if *hit {
+                write!(f, "*")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn point_name(point: u8) -> String {
+    match point {
+        BAR => "bar".to_string(),
+        OFF => "off".to_string(),
+        _ => point.to_string(),
+    }
+}
+
+fn parse_point(s: &str) -> anyhow::Result<u8> {
+    match s.to_ascii_lowercase().as_str() {
+        "bar" => Ok(BAR),
+        "off" => Ok(OFF),
+        _ => match s.parse::<u8>() {
+            Ok(point) if point <= BAR => Ok(point),
+            _ => Err(anyhow!("invalid point {:?}", s)),
+        },
+    }
+}
+
+/// `24/18*/13(2)` into its path and how many times it's repeated
+fn parse_move(s: &str) -> anyhow::Result<(Path, usize)> {
+    let (s, count) = match s.strip_suffix(')') {
+        Some(rest) => {
+            let (rest, count) = rest
+                .split_once('(')
+                .ok_or_else(|| anyhow!("unbalanced parentheses in {:?}", s))?;
+
+            match count.parse::<usize>() {
+                Ok(count) if (1..=4).contains(&count) => (rest, count),
+                _ => bail!("invalid repetition in {:?}", s),
+            }
+        }
+        None => (s, 1),
+    };
+
+    let mut points = s.split('/').map(|p| match p.strip_suffix('*') {
+        Some(p) => parse_point(p).map(|point| (point, true)),
+        None => parse_point(p).map(|point| (point, false)),
+    });
+
+    let (from, from_hit) = points
+        .next()
+        .ok_or_else(|| anyhow!("empty move in {:?}", s))??;
+    let to = points.collect::<anyhow::Result<Vec<_>>>()?;
+
+    if from_hit || to.is_empty() {
+        bail!("invalid move {:?}", s);
+    }
+
+    let mut prev = from;
+    for (point, _) in &to {
+        if *point >= prev {
+            bail!("move {:?} doesn't go forward", s);
+        }
+        prev = *point;
+    }
+
+    Ok((Path { from, to }, count))
+}
+
+fn parse_paths(s: &str) -> anyhow::Result<Vec<Path>> {
+    let mut paths = vec![];
+
+    for token in s.split(|c: char| c.is_whitespace() || c == ',') {
+        if token.is_empty() {
+            continue;
+        }
+
+        let (path, count) = parse_move(token)?;
+
+        for _ in 0..count {
+            paths.push(path.clone());
+        }
+    }
+
+    Ok(paths)
+}
+
+/// checkers of `player` gained or lost on each point, bar included
+fn net_change(before: &Board, after: &Board, player: Bw) -> [i8; 26] {
+    let mut delta = [0; 26];
+
+    for point in 1..=BAR {
+        let coord = BoardCoord::rel(player, point);
+        let count = |b: &Board| {
+            if b[coord].matches(player) {
+                b[coord].to_count() as i8
+            } else {
+                0
+            }
+        };
+
+        delta[point as usize] = count(after) - count(before);
+    }
+
+    delta
+}
+
+/// Whether every move in `paths` can be walked with the dice `play` uses, each
+/// die once. Every written point has to be one a checker lands on, but one
+/// move like `13/7` can stand for any steps in between.
+fn same_dice(paths: &[Path], play: &Play) -> bool {
+    let mut dice = play.steps().iter().map(|s| s.die.get()).collect::<Vec<_>>();
+
+    let segments = paths
+        .iter()
+        .flat_map(|path| {
+            path.to.iter().scan(path.from, |from, &(to, _)| {
+                Some((std::mem::replace(from, to), to))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    walk(&segments, &mut dice)
+}
+
+/// walks along each of `segments` in turn, using up all of `dice`
+fn walk(segments: &[(u8, u8)], dice: &mut Vec<u8>) -> bool {
+    let Some((&(from, to), rest)) = segments.split_first() else {
+        return dice.is_empty();
+    };
+
+    if from == to {
+        return walk(rest, dice);
+    }
+
+    for i in 0..dice.len() {
+        let die = dice[i];
+        if dice[..i].contains(&die) {
+            continue;
+        }
+
+        // only bearing off can take more than the distance
+        let next = match from.checked_sub(die) {
+            Some(next) if next >= to => next,
+            _ if to == OFF => OFF,
+            _ => continue,
+        };
+
+        dice.remove(i);
+        if walk(&[&[(next, to)], rest].concat(), dice) {
+            return true;
+        }
+        dice.insert(i, die);
+    }
+
+    false
+}
+
+fn play_paths(play: &Play) -> Vec<Path> {
+    let player = play.player;
+
+    // every checker only moves forward, so its steps come out in order
+    let steps = play
+        .steps()
+        .iter()
+        .sorted_by_key(|s| std::cmp::Reverse(s.from.perspective(player)));
+
+    let mut paths: Vec<Path> = vec![];
+
+    for step in steps {
+        let from = step.from.perspective(player);
+        let to = (step.to.perspective(player), step.hit);
+
+        match paths.iter_mut().find(|p| p.end() == from) {
+            Some(path) => path.to.push(to),
+            None => paths.push(Path { from, to: vec![to] }),
+        }
+    }
+
+    paths.into_iter().map(Path::simplified).collect()
+}
+
+impl Display for Play {
+    /// canonical notation: repeated moves merged as `8/4(2)`, hits marked with `*`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let moves = play_paths(self)
+            .into_iter()
+            .sorted_by(|a, b| (b.from, b.end()).cmp(&(a.from, a.end())))
+            .dedup_with_count()
+            .map(|(count, path)| {
+                if count > 1 {
+                    format!("{}({})", path, count)
+                } else {
+                    path.to_string()
+                }
+            })
+            .join(" ");
+
+        f.write_str(&moves)
+    }
+}
+
+/// Finds the legal play of `dice` on `board` written as `notation`.
+///
+/// An empty notation is accepted only when there's no legal play.
+pub fn parse_play<Generator: MoveGen>(
+    board: &Board,
+    dice: Dice,
+    player: Bw,
+    notation: &str,
+) -> anyhow::Result<(Play, Board)> {
+    let paths = parse_paths(notation)?;
+    let plays = Generator::gen_unique_plays(board, dice, player);
+
+    if paths.is_empty() {
+        if plays.is_empty() {
+            return Ok((Play::new(player), board.clone()));
+        }
+
+        bail!(
+            "no move given, but {} has legal plays with {:?}",
+            player,
+            dice
+        );
+    }
+
+    let mut delta = [0i8; 26];
+    let mut hits = vec![];
+
+    for path in &paths {
+        delta[path.from as usize] -= 1;
+        if path.end() != OFF {
+            delta[path.end() as usize] += 1;
+        }
+
+        hits.extend(path.to.iter().filter(|(_, hit)| *hit).map(|(p, _)| *p));
+    }
+    hits.sort();
+
+    let candidates = plays
+        .into_iter()
+        .filter(|(play, b)| net_change(board, b, player) == delta && same_dice(&paths, play))
+        .collect::<Vec<_>>();
+
+    let play_hits = |play: &Play| {
+        play.steps()
+            .iter()
+            .filter(|s| s.hit)
+            .map(|s| s.to.perspective(player))
+            .sorted()
+            .collect::<Vec<_>>()
+    };
+
+    if let Some(found) = candidates.iter().find(|(play, _)| play_hits(play) == hits) {
+        return Ok(found.clone());
+    }
+
+    // logs often leave out the hit marks
+    if hits.is_empty() && candidates.len() == 1 {
+        return Ok(candidates[0].clone());
+    }
+
+    if candidates.is_empty() {
+        bail!(
+            "{:?} is not a legal play for {} with {:?}",
+            notation,
+            player,
+            dice
+        )
+    } else {
+        bail!(
+            "{:?} marks the wrong hits for {} with {:?}, legal: {}",
+            notation,
+            player,
+            dice,
+            candidates
+                .iter()
+                .map(|(play, _)| play.to_string())
+                .join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        movegen::simd::Simd1MoveGenerator,
+        types::{
+            board::{Board, BoardCoord},
+            dice::{Dice, Die},
+            prim::{BOrW, Bw},
+        },
+    };
+
+    use super::parse_play;
+
+    fn parse(board: &Board, dice: (u8, u8), player: Bw, s: &str) -> anyhow::Result<Board> {
+        let dice = Dice(Die(dice.0), Die(dice.1));
+        let (play, after) = parse_play::<Simd1MoveGenerator>(board, dice, player, s)?;
+
+        let mut applied = board.clone();
+        applied.apply(&play);
+        assert_eq!(applied, after);
+
+        Ok(after)
+    }
+
+    fn format(board: &Board, dice: (u8, u8), player: Bw, s: &str) -> String {
+        let dice = Dice(Die(dice.0), Die(dice.1));
+
+        parse_play::<Simd1MoveGenerator>(board, dice, player, s)
+            .unwrap()
+            .0
+            .to_string()
+    }
+
+    #[test]
+    fn test_opening_moves() {
+        let board = Board::default();
+
+        let white = parse(&board, (3, 1), Bw::White, "8/5 6/5").unwrap();
+        assert_eq!(white[BoardCoord(5)], BOrW::white(2));
+
+        let black = parse(&board, (1, 3), Bw::Black, "8/5 6/5").unwrap();
+        assert_eq!(black.inverse(), white);
+
+        assert_eq!(format(&board, (3, 1), Bw::White, "6/5, 8/5"), "8/5 6/5");
+        assert_eq!(format(&board, (6, 5), Bw::White, "24/18/13"), "24/13");
+        assert_eq!(
+            format(&board, (2, 2), Bw::White, "8/6 8/6 6/4 6/4"),
+            "8/4(2)"
+        );
+        assert_eq!(format(&board, (2, 2), Bw::White, "8/4(2)"), "8/4(2)");
+    }
+
+    #[test]
+    fn test_hits() {
+        let mut board = Board::default();
+        board[BoardCoord(12)] = BOrW::black(4);
+        board[BoardCoord(11)] = BOrW::black(1);
+
+        let after = parse(&board, (6, 2), Bw::White, "24/18 13/11*").unwrap();
+        assert_eq!(after[BoardCoord::bar(Bw::Black)], BOrW::black(1));
+
+        // without the mark there's still only one way to get there
+        assert_eq!(
+            parse(&board, (6, 2), Bw::White, "24/18 13/11").unwrap(),
+            after
+        );
+        assert_eq!(
+            format(&board, (6, 2), Bw::White, "13/11 24/18"),
+            "24/18 13/11*"
+        );
+
+        assert!(parse(&board, (6, 2), Bw::White, "24/18* 13/11").is_err());
+
+        // hit on the way
+        assert_eq!(format(&board, (2, 1), Bw::White, "13/11*/10"), "13/11*/10");
+        assert_eq!(format(&board, (2, 1), Bw::White, "13/10"), "13/11*/10");
+    }
+
+    #[test]
+    fn test_bar_and_off() {
+        let board: Board = [
+            (BoardCoord::bar(Bw::White), BOrW::white(1)),
+            (BoardCoord(6), BOrW::white(14)),
+            (BoardCoord(20), BOrW::black(15)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            format(&board, (3, 1), Bw::White, "bar/22 6/5"),
+            "bar/22 6/5"
+        );
+        assert_eq!(format(&board, (3, 1), Bw::White, "25/21"), "bar/21");
+
+        let home: Board = [
+            (BoardCoord(6), BOrW::white(2)),
+            (BoardCoord(20), BOrW::black(15)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(format(&home, (6, 6), Bw::White, "6/off(2)"), "6/off(2)");
+        assert_eq!(format(&home, (6, 1), Bw::White, "6/off 6/5"), "6/5 6/off");
+    }
+
+    #[test]
+    fn test_errors() {
+        let board = Board::default();
+
+        assert!(parse(&board, (3, 1), Bw::White, "24/10").is_err());
+        assert!(parse(&board, (3, 1), Bw::White, "8/5").is_err());
+        assert!(parse(&board, (3, 1), Bw::White, "foo").is_err());
+        assert!(parse(&board, (3, 1), Bw::White, "5/8 6/5").is_err());
+        assert!(parse(&board, (3, 1), Bw::White, "8/5(7)").is_err());
+        assert!(parse(&board, (3, 1), Bw::White, "").is_err());
+
+        // the right checkers end up in the right places, but not by the dice
+        assert!(parse(&board, (3, 1), Bw::White, "8/7 7/5 6/5").is_err());
+        assert!(parse(&board, (6, 1), Bw::White, "13/10 10/6").is_err());
+        assert!(parse(&board, (6, 1), Bw::White, "13/10/6").is_err());
+        assert!(parse(&board, (6, 1), Bw::White, "13/7 7/6").is_ok());
+        assert!(parse(&board, (6, 1), Bw::White, "13/12/6").is_ok());
+        assert!(parse(&board, (6, 1), Bw::White, "13/6").is_ok());
+    }
+}
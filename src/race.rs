@@ -0,0 +1,280 @@
+//! Race counts: the Thorp and Keith counts, the 8-9-12 rule and effective pip
+//! counts, each with the cube action its author recommends.
+//!
+//! Everything is from the side of `player`, who is on roll and thinking of
+//! doubling. The counts only make sense once [`is_contact`] says the race is
+//! on.
+
+use crate::{
+    bearoff::one_sided::BearoffDb,
+    types::{
+        board::{Board, BoardCoord},
+        prim::Bw,
+    },
+};
+
+/// pips an average roll is worth
+pub const PIPS_PER_ROLL: f32 = 49.0 / 6.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CubeAdvice {
+    /// a centered cube should be turned
+    pub double: bool,
+    /// so should a cube the player on roll owns
+    pub redouble: bool,
+    /// and the opponent should take it
+    pub take: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RaceCount {
+    /// the count of the player on roll, adjusted as the method says
+    pub own: f32,
+    pub opponent: f32,
+    pub cube: CubeAdvice,
+}
+
+/// Whether some checkers still have to pass each other.
+pub fn is_contact(board: &Board) -> bool {
+    let lanes = board.0.as_array();
+
+    // White moves down to lane 1 and Black up to lane 24
+    let white_back = (0..26).rev().find(|i| lanes[*i] > 0);
+    let black_back = (0..26).find(|i| lanes[*i] < 0);
+
+    matches!((white_back, black_back), (Some(w), Some(b)) if w > b)
+}
+
+fn checkers(board: &Board, color: Bw, point: u8) -> u32 {
+    let v = board[BoardCoord::rel(color, point)];
+    if v.matches(color) {
+        v.to_count() as u32
+    } else {
+        0
+    }
+}
+
+/// Pips, plus 2 for every checker left, plus 1 for every checker on the
+/// 1 point, minus 1 for every home point occupied.
+pub fn thorp_count(board: &Board, color: Bw) -> u32 {
+    let left: u32 = (1..=25).map(|i| checkers(board, color, i)).sum();
+    let home_points = (1..=6).filter(|i| checkers(board, color, *i) > 0).count() as u32;
+
+    (board.pip_count(color) + 2 * left + checkers(board, color, 1)).saturating_sub(home_points)
+}
+
+/// Edward O. Thorp's rule: the leader's count goes up by a tenth over 30.
+/// Double if the opponent's count is at most 2 below it, redouble if at most 1,
+/// and the opponent takes unless theirs is more than 2 above it.
+pub fn thorp(board: &Board, player: Bw) -> RaceCount {
+    let mut own = thorp_count(board, player);
+    if own > 30 {
+        own += own / 10;
+    }
+
+    let (own, opponent) = (own as i32, thorp_count(board, -player) as i32);
+
+    RaceCount {
+        own: own as f32,
+        opponent: opponent as f32,
+        cube: CubeAdvice {
+            double: opponent >= own - 2,
+            redouble: opponent >= own - 1,
+            take: opponent <= own + 2,
+        },
+    }
+}
+
+/// Pips, plus 2 for every checker beyond the first on the 1 point, 1 for every
+/// one beyond the first on the 2 point and beyond the third on the 3 point,
+/// and 1 for each of the 4, 5 and 6 points that is empty.
+pub fn keith_count(board: &Board, color: Bw) -> u32 {
+    let on = |point| checkers(board, color, point);
+    let gaps = (4..=6).filter(|i| on(*i) == 0).count() as u32;
+
+    board.pip_count(color)
+        + 2 * on(1).saturating_sub(1)
+        + on(2).saturating_sub(1)
+        + on(3).saturating_sub(3)
+        + gaps
+}
+
+/// Tom Keith's rule: the player on roll adds a seventh to their count, then
+/// doubles if it is at most 4 more than the opponent's, redoubles if at most 3,
+/// and the opponent takes if it is at least 2 more.
+pub fn keith(board: &Board, player: Bw) -> RaceCount {
+    let own = keith_count(board, player) as f32 * 8.0 / 7.0;
+    let opponent = keith_count(board, -player) as f32;
+
+    RaceCount {
+        own,
+        opponent,
+        cube: CubeAdvice {
+            double: own - opponent <= 4.0,
+            redouble: own - opponent <= 3.0,
+            take: own - opponent >= 2.0,
+        },
+    }
+}
+
+fn by_lead(own: f32, opponent: f32) -> CubeAdvice {
+    let lead = (opponent - own) / own;
+
+    CubeAdvice {
+        double: lead >= 0.08,
+        redouble: lead >= 0.09,
+        take: lead <= 0.12,
+    }
+}
+
+/// The 8-9-12 rule on plain pip counts: double with a lead of 8% of your own
+/// count, redouble with 9%, take if it's 12% or less.
+pub fn eight_nine_twelve(board: &Board, player: Bw) -> RaceCount {
+    let own = board.pip_count(player) as f32;
+    let opponent = board.pip_count(-player) as f32;
+
+    RaceCount {
+        own,
+        opponent,
+        cube: by_lead(own, opponent),
+    }
+}
+
+/// Walter Trice's effective pip count, exact from a one-sided database: the
+/// rolls a side needs on average, in pips. `None` unless both sides are
+/// bearing off with few enough checkers for `db`. The cube follows the 8-9-12
+/// percentages, on effective pips.
+pub fn epc<B: AsRef<[u8]>>(db: &BearoffDb<B>, board: &Board, player: Bw) -> Option<RaceCount> {
+    let own = db.expected_rolls(board, player)? * PIPS_PER_ROLL;
+    let opponent = db.expected_rolls(board, -player)? * PIPS_PER_ROLL;
+
+    Some(RaceCount {
+        own,
+        opponent,
+        cube: by_lead(own, opponent),
+    })
+}
+
+/// what `color` loses to wasted pips: effective minus plain pip count
+pub fn wastage<B: AsRef<[u8]>>(db: &BearoffDb<B>, board: &Board, color: Bw) -> Option<f32> {
+    Some(db.expected_rolls(board, color)? * PIPS_PER_ROLL - board.pip_count(color) as f32)
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::{
+        bearoff::{home_board, one_sided::BearoffDb},
+        types::{
+            board::{Board, BoardCoord},
+            prim::{BOrW, Bw},
+        },
+    };
+
+    use super::{eight_nine_twelve, epc, is_contact, keith, keith_count, thorp, thorp_count};
+
+    #[test]
+    fn test_pip_count() {
+        assert_eq!(Board::default().pip_count(Bw::White), 167);
+        assert_eq!(Board::default().pip_count(Bw::Black), 167);
+
+        let rng = &mut rand::thread_rng();
+        for _ in 0..100 {
+            let board: Board = rng.gen();
+
+            for color in [Bw::White, Bw::Black] {
+                let pips: u32 = (1..=25)
+                    .map(|i| board[BoardCoord::rel(color, i)])
+                    .zip(1..)
+                    .filter(|(v, _)| v.matches(color))
+                    .map(|(v, i)| v.to_count() as u32 * i)
+                    .sum();
+
+                assert_eq!(board.pip_count(color), pips);
+                assert_eq!(board.inverse().pip_count(-color), pips);
+            }
+        }
+    }
+
+    #[test]
+    fn test_contact() {
+        assert!(is_contact(&Board::default()));
+        assert!(!is_contact(&Board::empty()));
+
+        let mut board: Board = [
+            (BoardCoord(12), BOrW::white(1)),
+            (BoardCoord(13), BOrW::black(1)),
+        ]
+        .into_iter()
+        .collect();
+        assert!(!is_contact(&board));
+
+        board.inc_bar(Bw::Black);
+        assert!(is_contact(&board));
+    }
+
+    #[test]
+    fn test_counts() {
+        // 3 on the 1 point, 2 on the 2, 4 on the 3 and 1 on the 6: 25 pips
+        let board = home_board(&[3, 2, 4, 0, 0, 1], Bw::White);
+
+        assert_eq!(thorp_count(&board, Bw::White), 25 + 20 + 3 - 4);
+        assert_eq!(keith_count(&board, Bw::White), 25 + 4 + 1 + 1 + 2);
+        assert_eq!(keith_count(&board.inverse(), Bw::Black), 33);
+    }
+
+    #[test]
+    fn test_cube() {
+        // 56 pips each, on the 6, 5 and 4 points
+        let even: Board = [
+            (BoardCoord(6), BOrW::white(4)),
+            (BoardCoord(5), BOrW::white(4)),
+            (BoardCoord(4), BOrW::white(3)),
+            (BoardCoord(19), BOrW::black(4)),
+            (BoardCoord(20), BOrW::black(4)),
+            (BoardCoord(21), BOrW::black(3)),
+        ]
+        .into_iter()
+        .collect();
+
+        for count in [thorp, keith, eight_nine_twelve] {
+            let cube = count(&even, Bw::White).cube;
+            assert!(!cube.double && cube.take, "{:?}", count(&even, Bw::White));
+        }
+
+        // one of Black's checkers is back on their 12 point: 62 pips, a
+        // market loser that's still a take by every count
+        let mut ahead = even.clone();
+        ahead[BoardCoord(19)] = BOrW::black(3);
+        ahead[BoardCoord(13)] = BOrW::black(1);
+
+        for count in [thorp, keith, eight_nine_twelve] {
+            let cube = count(&ahead, Bw::White).cube;
+            assert!(cube.double && cube.redouble && cube.take);
+        }
+
+        // Thorp's take line: 75 pips, +10% is 82, against 84 is a take and
+        // 85 a pass
+        let mut trailing = even.clone();
+        trailing[BoardCoord(19)] = BOrW::black(3);
+        trailing[BoardCoord(10)] = BOrW::black(1);
+        assert_eq!(thorp_count(&even, Bw::White), 75);
+        assert_eq!(thorp(&trailing, Bw::White).opponent, 84.0);
+        assert!(thorp(&trailing, Bw::White).cube.take);
+
+        trailing[BoardCoord(10)] = BOrW::empty();
+        trailing[BoardCoord(9)] = BOrW::black(1);
+        assert!(!thorp(&trailing, Bw::White).cube.take);
+
+        let db = BearoffDb::generate(3);
+        let mut bearoff = home_board(&[0, 1, 0, 0, 0, 2], Bw::White);
+        bearoff[BoardCoord(19)] = BOrW::black(2);
+        bearoff[BoardCoord(23)] = BOrW::black(1);
+
+        let count = epc(&db, &bearoff, Bw::White).unwrap();
+        assert_eq!(count.own, count.opponent);
+        assert!(count.own > bearoff.pip_count(Bw::White) as f32);
+        assert!(epc(&db, &even, Bw::White).is_none());
+    }
+}
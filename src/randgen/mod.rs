@@ -1,10 +1,13 @@
+use std::collections::HashMap;
+
 use once_cell::sync::Lazy;
 
-use rand::{distributions::Standard, prelude::Distribution};
+use rand::{distributions::Standard, prelude::Distribution, Rng};
 use rand_distr::WeightedAliasIndex;
 
 use crate::types::{
     board::{Board, BoardCoord},
+    class::PositionClass,
     prim::Bw,
 };
 
@@ -101,3 +104,34 @@ impl Distribution<Board> for Standard {
         board
     }
 }
+
+/// How many of `samples` random boards, with a random side on roll, fall in
+/// each class.
+pub fn class_distribution<R: Rng + ?Sized>(
+    rng: &mut R,
+    samples: usize,
+) -> HashMap<PositionClass, usize> {
+    let mut classes = HashMap::new();
+
+    for _ in 0..samples {
+        let board: Board = rng.gen();
+        *classes.entry(board.classify(rng.gen())).or_insert(0) += 1;
+    }
+
+    classes
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::class::PositionClass;
+
+    use super::class_distribution;
+
+    #[test]
+    fn test_class_distribution() {
+        let classes = class_distribution(&mut rand::thread_rng(), 1000);
+
+        assert_eq!(classes.values().sum::<usize>(), 1000);
+        assert!(classes[&PositionClass::Contact] > 100);
+    }
+}
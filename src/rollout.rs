@@ -0,0 +1,292 @@
+//! Monte Carlo rollouts: play a position out many times and average the results.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    decision::MoveDecision,
+    eval::{Evaluator, Probabilities},
+    game::{Game, GameState},
+    search::Search,
+    types::{board::Board, dice::Dice, prim::Bw},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RolloutSettings {
+    pub trials: u32,
+    /// Trial `i` is played with an RNG seeded with `seed + i`, so every
+    /// candidate sees the same dice.
+    pub seed: u64,
+    /// stop after this many rolls and ask the evaluator instead
+    pub truncate: Option<u32>,
+    /// go through all 36 first rolls in turn instead of rolling them
+    pub rotate_first_roll: bool,
+    /// Subtract the luck of every roll, as the evaluator sees it, from the
+    /// result. Costs a 1-ply evaluation per roll.
+    pub luck_adjustment: bool,
+}
+
+impl Default for RolloutSettings {
+    fn default() -> Self {
+        Self {
+            trials: 1296,
+            seed: 0,
+            truncate: None,
+            rotate_first_roll: true,
+            luck_adjustment: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RolloutResult {
+    pub trials: u32,
+    /// mean cubeless equity, luck adjusted if asked to
+    pub equity: f32,
+    pub std_error: f32,
+    /// average outcome: how often the game was won, gammons won and lost, and so on
+    pub probabilities: Probabilities,
+}
+
+/// Rolls out positions with `policy` playing both sides.
+pub struct Rollout<E, D> {
+    pub evaluator: E,
+    pub policy: D,
+    pub settings: RolloutSettings,
+}
+
+impl<E: Evaluator, D: MoveDecision> Rollout<E, D> {
+    pub fn new(evaluator: E, policy: D, settings: RolloutSettings) -> Self {
+        Self {
+            evaluator,
+            policy,
+            settings,
+        }
+    }
+
+    /// Rolls out the position after `player` has moved to `board`.
+    pub fn rollout(&mut self, board: &Board, player: Bw) -> RolloutResult {
+        if let Some(p) = Probabilities::finished(board, player) {
+            return RolloutResult {
+                trials: self.settings.trials,
+                equity: p.equity(),
+                std_error: 0.0,
+                probabilities: p,
+            };
+        }
+
+        let mut sum = 0.0f64;
+        let mut sum_sq = 0.0f64;
+        let mut probabilities = Probabilities::default();
+
+        for trial in 0..self.settings.trials {
+            let (p, luck) = self.trial(board, player, trial);
+            let equity = (p.equity() - luck) as f64;
+
+            sum += equity;
+            sum_sq += equity * equity;
+            probabilities += p;
+        }
+
+        let n = self.settings.trials.max(1) as f64;
+        let mean = sum / n;
+        let variance = if n > 1.0 {
+            ((sum_sq - sum * mean) / (n - 1.0)).max(0.0)
+        } else {
+            0.0
+        };
+
+        RolloutResult {
+            trials: self.settings.trials,
+            equity: mean as f32,
+            std_error: (variance / n).sqrt() as f32,
+            probabilities: probabilities * (1.0 / n as f32),
+        }
+    }
+
+    /// `rollout` for every candidate move of `player`
+    pub fn rollout_all(&mut self, candidates: &[Board], player: Bw) -> Vec<RolloutResult> {
+        candidates
+            .iter()
+            .map(|board| self.rollout(board, player))
+            .collect()
+    }
+
+    /// the outcome for `player` and the luck they had on the way
+    fn trial(&mut self, board: &Board, player: Bw, trial: u32) -> (Probabilities, f32) {
+        let mut rng = StdRng::seed_from_u64(self.settings.seed.wrapping_add(trial as u64));
+
+        let first = if self.settings.rotate_first_roll {
+            Dice::iter_all_possible().nth(trial as usize % 36).unwrap()
+        } else {
+            rng.gen()
+        };
+
+        let mut game = Game {
+            board: board.clone(),
+            state: GameState::Dice(-player, first),
+            cube: None,
+            match_score: None,
+        };
+
+        let evaluator = &self.evaluator;
+        let search = Search::new(|b: &Board, p: Bw| evaluator.evaluate(b, p), 0);
+
+        let mut luck = 0.0;
+        let mut rolls = 0;
+
+        loop {
+            let (on_roll, dice) = match game.state {
+                GameState::Dice(on_roll, dice) => (on_roll, dice),
+                GameState::Finished(_) => {
+                    let p = Probabilities::finished(&game.board, player)
+                        .expect("finished without a winner on the board");
+
+                    return (p, luck);
+                }
+                _ => unreachable!("rollouts are cubeless"),
+            };
+
+            if self.settings.truncate == Some(rolls) {
+                let p = self.evaluator.evaluate(&game.board, on_roll);
+                let p = if on_roll == player { p } else { p.invert() };
+
+                return (p, luck);
+            }
+
+            if self.settings.luck_adjustment {
+                // how much better this roll is than the average one
+                let rolled = search.best_move(&game.board, dice, on_roll, 0).1;
+                let average = search.evaluate_on_roll(&game.board, on_roll, 1);
+                let roll_luck = rolled.equity() - average.equity();
+
+                luck += if on_roll == player {
+                    roll_luck
+                } else {
+                    -roll_luck
+                };
+            }
+
+            rolls += 1;
+            game.play_self(&mut rng, &mut self.policy).next();
+        }
+    }
+}
+
+impl<E: Evaluator, D: MoveDecision> MoveDecision for Rollout<E, D> {
+    fn choose(&mut self, player: Bw, moves: Vec<Board>) -> Board {
+        let results = self.rollout_all(&moves, player);
+
+        moves
+            .into_iter()
+            .zip(results)
+            .max_by(|a, b| a.1.equity.total_cmp(&b.1.equity))
+            .expect("no moves to choose from")
+            .0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        decision::{greedy::GreedyDecision, RandomMoveDecision},
+        eval::PipCountEvaluator,
+        types::{
+            board::{Board, BoardCoord},
+            prim::{BOrW, Bw},
+        },
+    };
+
+    use super::{Rollout, RolloutSettings};
+
+    /// White's last checker is on its 1 point and Black, on roll, needs 3-3 or
+    /// better doubles to get its two checkers off first
+    fn last_roll() -> Board {
+        [
+            (BoardCoord(1), BOrW::white(1)),
+            (BoardCoord(19), BOrW::black(2)),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_rotated_first_roll() {
+        let settings = RolloutSettings {
+            trials: 36,
+            ..Default::default()
+        };
+        let mut rollout = Rollout::new(
+            PipCountEvaluator,
+            RandomMoveDecision(rand::thread_rng()),
+            settings,
+        );
+
+        let result = rollout.rollout(&last_roll(), Bw::White);
+
+        assert!((result.equity - 28.0 / 36.0).abs() < 1e-6);
+        assert!((result.probabilities.win - 32.0 / 36.0).abs() < 1e-6);
+        assert_eq!(result.probabilities.lose_gammon, 0.0);
+        assert!(result.std_error > 0.0);
+
+        let mut rollout = Rollout::new(
+            PipCountEvaluator,
+            RandomMoveDecision(rand::thread_rng()),
+            RolloutSettings {
+                luck_adjustment: true,
+                ..settings
+            },
+        );
+        let adjusted = rollout.rollout(&last_roll(), Bw::White);
+
+        assert!((adjusted.equity - result.equity).abs() < 1e-4);
+        assert!(adjusted.std_error < result.std_error / 2.0);
+    }
+
+    #[test]
+    fn test_seeded() {
+        let board = Board::default();
+        let settings = RolloutSettings {
+            trials: 20,
+            seed: 42,
+            truncate: Some(10),
+            ..Default::default()
+        };
+
+        let mut first = Rollout::new(
+            PipCountEvaluator,
+            GreedyDecision(PipCountEvaluator),
+            settings,
+        );
+        let mut second = Rollout::new(
+            PipCountEvaluator,
+            GreedyDecision(PipCountEvaluator),
+            settings,
+        );
+
+        assert_eq!(
+            first.rollout(&board, Bw::White),
+            second.rollout(&board, Bw::White)
+        );
+        assert_eq!(
+            first
+                .rollout_all(&[Board::default(), board], Bw::Black)
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_finished() {
+        let board: Board = [(BoardCoord(19), BOrW::black(15))].into_iter().collect();
+
+        let mut rollout = Rollout::new(
+            PipCountEvaluator,
+            GreedyDecision(PipCountEvaluator),
+            RolloutSettings::default(),
+        );
+        let result = rollout.rollout(&board, Bw::White);
+
+        assert_eq!(result.equity, 2.0);
+        assert_eq!(result.std_error, 0.0);
+    }
+}
@@ -0,0 +1,285 @@
+//! N-ply expectiminimax: the player picks their best move, the dice are averaged.
+//!
+//! Plies are counted like GNUBG does: 0-ply is the evaluator on the position
+//! after the move, 1-ply also tries every roll and reply of the opponent, and
+//! so on.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use itertools::Itertools;
+
+use crate::{
+    decision::MoveDecision,
+    eval::{Evaluator, Probabilities},
+    movegen::{simd::Simd1MoveGenerator, MoveGen},
+    types::{board::Board, dice::Dice, prim::Bw},
+    zobrist::TranspositionTable,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Which moves, ranked at 0-ply, are worth searching deeper.
+pub struct MoveFilter {
+    /// the best `keep` moves always make it
+    pub keep: usize,
+    /// and so does anything this close in equity to the best one
+    pub threshold: f32,
+}
+
+impl Default for MoveFilter {
+    fn default() -> Self {
+        Self {
+            keep: 8,
+            threshold: 0.16,
+        }
+    }
+}
+
+impl MoveFilter {
+    /// `scored` has to be sorted by equity, best first
+    pub fn cut<T>(&self, scored: &mut Vec<(T, f32)>) {
+        let Some(best) = scored.first().map(|(_, equity)| *equity) else {
+            return;
+        };
+
+        let mut i = 0;
+        scored.retain(|(_, equity)| {
+            i += 1;
+            i <= self.keep || *equity >= best - self.threshold
+        });
+    }
+}
+
+pub struct Search<E, Generator: MoveGen = Simd1MoveGenerator> {
+    pub evaluator: E,
+    pub plies: u8,
+    pub filter: MoveFilter,
+    /// Remembers on-roll evaluations of 1 ply and deeper. Only share it
+    /// between searches with the same evaluator and filter.
+    pub table: Option<Arc<TranspositionTable>>,
+    generator: PhantomData<Generator>,
+}
+
+impl<E: Evaluator> Search<E> {
+    pub fn new(evaluator: E, plies: u8) -> Self {
+        Self {
+            evaluator,
+            plies,
+            filter: MoveFilter::default(),
+            table: None,
+            generator: PhantomData,
+        }
+    }
+}
+
+impl<E: Evaluator, Generator: MoveGen> Search<E, Generator> {
+    #[must_use]
+    pub fn with_filter(self, filter: MoveFilter) -> Self {
+        Self { filter, ..self }
+    }
+
+    #[must_use]
+    pub fn with_table(self, table: Arc<TranspositionTable>) -> Self {
+        Self {
+            table: Some(table),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_generator<G: MoveGen>(self) -> Search<E, G> {
+        Search {
+            evaluator: self.evaluator,
+            plies: self.plies,
+            filter: self.filter,
+            table: self.table,
+            generator: PhantomData,
+        }
+    }
+
+    /// Chances of `player`, who's on roll, averaged over all 21 rolls.
+    pub fn evaluate_on_roll(&self, board: &Board, player: Bw, plies: u8) -> Probabilities {
+        if plies == 0 {
+            return self.evaluator.evaluate(board, player);
+        }
+
+        let key = self.table.as_ref().map(|_| board.zobrist_on_roll(player));
+        if let (Some(table), Some(key)) = (&self.table, key) {
+            if let Some(p) = table.get(key, plies) {
+                return p;
+            }
+        }
+
+        let p = Dice::iter_unique()
+            .map(|(dice, n)| {
+                let (_, p) = self.best_move(board, dice, player, plies - 1);
+                p * (n as f32 / 36.0)
+            })
+            .fold(Probabilities::default(), |acc, p| acc + p);
+
+        if let (Some(table), Some(key)) = (&self.table, key) {
+            table.insert(key, plies, p);
+        }
+
+        p
+    }
+
+    /// Chances of `player` after they've moved to `board`.
+    pub fn evaluate_after(&self, board: &Board, player: Bw, plies: u8) -> Probabilities {
+        if plies == 0 {
+            return self.evaluator.evaluate_after(board, player);
+        }
+
+        match Probabilities::finished(board, player) {
+            Some(p) => p,
+            None => self.evaluate_on_roll(board, -player, plies).invert(),
+        }
+    }
+
+    /// `player`'s best move with `dice` and its chances, searched `plies` deep.
+    /// With no legal move the board stays as it is.
+    pub fn best_move(
+        &self,
+        board: &Board,
+        dice: Dice,
+        player: Bw,
+        plies: u8,
+    ) -> (Board, Probabilities) {
+        let moves = Generator::gen_unique_moves(board, dice, player);
+
+        if moves.is_empty() {
+            return (board.clone(), self.evaluate_after(board, player, plies));
+        }
+
+        self.choose_from(moves, player, plies)
+    }
+
+    fn choose_from(&self, moves: Vec<Board>, player: Bw, plies: u8) -> (Board, Probabilities) {
+        let mut scored = moves
+            .into_iter()
+            .map(|board| {
+                let p = self.evaluator.evaluate_after(&board, player);
+                ((board, p), p.equity())
+            })
+            .collect_vec();
+
+        if plies > 0 {
+            scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+            self.filter.cut(&mut scored);
+
+            for ((board, p), equity) in scored.iter_mut() {
+                *p = self.evaluate_after(board, player, plies);
+                *equity = p.equity();
+            }
+        }
+
+        scored
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("no moves to choose from")
+            .0
+    }
+}
+
+impl<E: Evaluator, Generator: MoveGen> MoveDecision for Search<E, Generator> {
+    fn choose(&mut self, player: Bw, moves: Vec<Board>) -> Board {
+        self.choose_from(moves, player, self.plies).0
+    }
+}
+
+/// A search is an evaluator itself, `plies` deeper than the one it's built on.
+impl<E: Evaluator, Generator: MoveGen> Evaluator for Search<E, Generator> {
+    fn evaluate(&self, board: &Board, player: Bw) -> Probabilities {
+        self.evaluate_on_roll(board, player, self.plies)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use rand::Rng;
+
+    use crate::{
+        decision::{greedy::GreedyDecision, MoveDecision},
+        eval::{Evaluator, PipCountEvaluator},
+        movegen::{simd::Simd1MoveGenerator, MoveGen},
+        types::{
+            board::{Board, BoardCoord},
+            prim::{BOrW, Bw},
+        },
+        zobrist::TranspositionTable,
+    };
+
+    use super::{MoveFilter, Search};
+
+    #[test]
+    fn test_filter() {
+        let filter = MoveFilter {
+            keep: 2,
+            threshold: 0.1,
+        };
+
+        let mut scored = vec![("a", 0.5), ("b", 0.1), ("c", 0.05), ("d", 0.0)];
+        filter.cut(&mut scored);
+        assert_eq!(scored, vec![("a", 0.5), ("b", 0.1)]);
+
+        let mut scored = vec![("a", 0.5), ("b", 0.45), ("c", 0.42), ("d", 0.3)];
+        filter.cut(&mut scored);
+        assert_eq!(scored, vec![("a", 0.5), ("b", 0.45), ("c", 0.42)]);
+    }
+
+    #[test]
+    fn test_zero_ply_is_greedy() {
+        let rng = &mut rand::thread_rng();
+        let mut search = Search::new(PipCountEvaluator, 0);
+
+        for _ in 0..100 {
+            let board: Board = rng.gen();
+            let player = rng.gen();
+            let moves = Simd1MoveGenerator::gen_unique_moves(&board, rng.gen(), player);
+
+            if moves.is_empty() || board.winner().is_some() {
+                continue;
+            }
+
+            assert_eq!(
+                search.choose(player, moves.clone()),
+                GreedyDecision(PipCountEvaluator).choose(player, moves)
+            );
+        }
+    }
+
+    #[test]
+    fn test_race_to_the_end() {
+        // White's last checker is on its 1 point, Black has two on its 6 point
+        let board: Board = [
+            (BoardCoord(1), BOrW::white(1)),
+            (BoardCoord(19), BOrW::black(2)),
+        ]
+        .into_iter()
+        .collect();
+
+        // Black gets off first only with 3-3 or better doubles, and at two
+        // plies White's next roll is in the search too
+        let search = Search::new(PipCountEvaluator, 2);
+        let white = search.evaluate_after(&board, Bw::White, 2);
+        assert!((white.win - 32.0 / 36.0).abs() < 1e-6, "{:?}", white);
+
+        assert!((search.evaluate(&board, Bw::White).win - 1.0).abs() < 1e-6);
+        assert!((search.evaluate(&board, Bw::Black).win - white.lose()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_table() {
+        let table = Arc::new(TranspositionTable::new(1024));
+        let cached = Search::new(PipCountEvaluator, 1).with_table(table.clone());
+        let plain = Search::new(PipCountEvaluator, 1);
+
+        let board = Board::default();
+        let first = cached.evaluate(&board, Bw::White);
+
+        assert_eq!(first, plain.evaluate(&board, Bw::White));
+        assert_eq!(table.get(board.zobrist_on_roll(Bw::White), 1), Some(first));
+        assert_eq!(cached.evaluate(&board, Bw::White), first);
+    }
+}
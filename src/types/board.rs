@@ -3,12 +3,14 @@ use std::{
     simd::{Simd, SimdInt},
 };
 
+use anyhow::bail;
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
 use super::{
     dice::Die,
-    prim::{BOrW, Bw},
+    play::{Play, Step},
+    prim::{BOrW, Bw, WinKind},
 };
 
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
@@ -31,6 +33,13 @@ impl BoardCoord {
         }
     }
 
+    #[inline]
+    /// where borne-off checkers go: just past the end of the color's track,
+    /// i.e. the opponent's bar lane
+    pub fn off(color: Bw) -> BoardCoord {
+        BoardCoord::bar(-color)
+    }
+
     #[must_use]
     #[inline]
     pub fn rel(color: Bw, i: u8) -> BoardCoord {
@@ -63,6 +72,12 @@ impl BoardCoord {
         }
     }
 
+    #[must_use]
+    #[inline]
+    pub fn is_off(&self, color: Bw) -> bool {
+        *self == BoardCoord::off(color)
+    }
+
     #[must_use]
     #[inline]
     pub fn perspective(&self, color: Bw) -> u8 {
@@ -100,22 +115,39 @@ impl Add<(Bw, Die)> for BoardCoord {
     }
 }
 
+// Hash goes through the Zobrist key, see `zobrist`, and serde mostly through
+// `to_packed`
 #[derive(
-    Hash,
     Eq,
     PartialEq,
     // Copy,
     Clone,
-    // Serialize,
-    // Deserialize,
     PartialOrd,
     Ord,
 )]
 #[repr(transparent)]
-// #[serde(transparent)]
 /// from 1 to 24; 0 and 25 mean respective "bars"
 pub struct Board(pub Simd<i8, 32>);
 
+const WHITE_PIPS: Simd<i16, 32> = Simd::from_array(lane_pips(Bw::White));
+const BLACK_PIPS: Simd<i16, 32> = Simd::from_array(lane_pips(Bw::Black));
+
+/// what a checker of `color` in each lane is worth in pips
+const fn lane_pips(color: Bw) -> [i16; 32] {
+    let mut pips = [0; 32];
+
+    let mut lane = 0;
+    while lane < 26 {
+        pips[lane] = match color {
+            Bw::White => lane as i16,
+            Bw::Black => 25 - lane as i16,
+        };
+        lane += 1;
+    }
+
+    pips
+}
+
 impl Board {
     pub fn empty() -> Self {
         // Self([BOrW::empty(); 26])
@@ -146,6 +178,43 @@ impl Board {
         }
     }
 
+    /// A gammon if the loser hasn't borne off any checkers, a backgammon if they
+    /// also still have some on the bar or in the winner's home board.
+    pub fn win_kind(&self, winner: Bw) -> WinKind {
+        let loser = -winner;
+
+        let on_board = (0..=25)
+            .map(|i| self[BoardCoord(i)])
+            .filter(|v| v.matches(loser))
+            .map(|v| v.to_count() as u32)
+            .sum::<u32>();
+
+        if on_board < 15 {
+            return WinKind::Single;
+        }
+
+        let stuck = self[BoardCoord::bar(loser)].matches(loser)
+            || (1..=6).any(|i| self[BoardCoord::rel(winner, i)].matches(loser));
+
+        if stuck {
+            WinKind::Backgammon
+        } else {
+            WinKind::Gammon
+        }
+    }
+
+    /// Pips `color` still has to travel, the bar counting 25.
+    #[inline]
+    pub fn pip_count(&self, color: Bw) -> u32 {
+        // `(x + |x|) / 2` keeps White's checkers, `(|x| - x) / 2` Black's
+        let (checkers, pips) = match color {
+            Bw::White => (self.0 + self.0.abs(), WHITE_PIPS),
+            Bw::Black => (self.0.abs() - self.0, BLACK_PIPS),
+        };
+
+        ((checkers / Simd::splat(2)).cast::<i16>() * pips).reduce_sum() as u32
+    }
+
     #[inline]
     pub fn inc_bar(&mut self, color: Bw) {
         self.0[BoardCoord::bar(color).0 as usize] += match color {
@@ -156,12 +225,54 @@ impl Board {
 
     #[inline]
     pub fn dec_bar(&mut self, color: Bw) {
-        self[BoardCoord::bar(color)] -= match color {
+        self.0[BoardCoord::bar(color).0 as usize] -= match color {
             Bw::White => 1,
             Bw::Black => -1,
         };
     }
 
+    #[inline]
+    pub fn apply_step(&mut self, player: Bw, step: &Step) {
+        self[step.from] = (player, self[step.from].to_count() - 1).into();
+
+        if step.to.is_off(player) {
+            return;
+        }
+
+        if step.hit {
+            self[step.to] = (player, 1).into();
+            self.inc_bar(-player);
+        } else {
+            self[step.to] = (player, self[step.to].to_count() + 1).into();
+        }
+    }
+
+    #[inline]
+    pub fn unapply_step(&mut self, player: Bw, step: &Step) {
+        if !step.to.is_off(player) {
+            if step.hit {
+                self[step.to] = (-player, 1).into();
+                self.dec_bar(-player);
+            } else {
+                self[step.to] = (player, self[step.to].to_count() - 1).into();
+            }
+        }
+
+        self[step.from] = (player, self[step.from].to_count() + 1).into();
+    }
+
+    pub fn apply(&mut self, play: &Play) {
+        for step in play.steps() {
+            self.apply_step(play.player, step);
+        }
+    }
+
+    pub fn unapply(&mut self, play: &Play) {
+        for step in play.steps().iter().rev() {
+            self.unapply_step(play.player, step);
+        }
+    }
+
     pub fn inverse(&self) -> Board {
         let mut arr = [0; 32];
 
@@ -270,3 +381,261 @@ impl IndexMut<BoardCoord> for Board {
         unsafe { &mut *(self.0.index_mut(index.0 as usize) as *mut i8 as *mut BOrW) }
     }
 }
+
+/// What's wrong with a board that [`Board::validate`] turns down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoardError {
+    /// more than 15 checkers of the color on the board and bar
+    TooManyCheckers(Bw, u32),
+    /// the color's bar lane holds the opponent's checkers, which can only
+    /// mean it has borne off into the lane
+    WrongBar(Bw),
+    /// one of the unused lanes from 26 on isn't 0
+    Padding(usize),
+    /// nobody has any checkers left
+    BothBorneOff,
+}
+
+impl std::fmt::Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::TooManyCheckers(color, n) => write!(f, "{} has {} checkers", color, n),
+            BoardError::WrongBar(color) => write!(f, "{}'s bar has the opponent's checkers", color),
+            BoardError::Padding(lane) => write!(f, "unused lane {} isn't empty", lane),
+            BoardError::BothBorneOff => write!(f, "both sides have borne off"),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+impl Board {
+    /// Checks what the type can't: checker counts, bar colours, that lanes
+    /// from 26 on are empty and that somebody is still playing. Whether a game
+    /// could actually get there isn't checked.
+    pub fn validate(&self) -> Result<(), BoardError> {
+        let lanes = self.0.as_array();
+
+        if let Some(lane) = (26..32).find(|lane| lanes[*lane] != 0) {
+            return Err(BoardError::Padding(lane));
+        }
+
+        for color in [Bw::White, Bw::Black] {
+            if self[BoardCoord::bar(color)].matches(-color) {
+                return Err(BoardError::WrongBar(color));
+            }
+
+            let checkers: u32 = lanes[..26]
+                .iter()
+                .filter(|v| BOrW(**v).matches(color))
+                .map(|v| v.unsigned_abs() as u32)
+                .sum();
+
+            if checkers > 15 {
+                return Err(BoardError::TooManyCheckers(color, checkers));
+            }
+        }
+
+        if self.is_winner(Bw::White) && self.is_winner(Bw::Black) {
+            return Err(BoardError::BothBorneOff);
+        }
+
+        Ok(())
+    }
+}
+
+/// lanes 0 to 25, checked by [`Board::validate`]
+impl TryFrom<[i8; 26]> for Board {
+    type Error = BoardError;
+
+    fn try_from(lanes: [i8; 26]) -> Result<Self, Self::Error> {
+        let mut arr = [0; 32];
+        arr[..26].copy_from_slice(&lanes);
+
+        let board = Board(Simd::from(arr));
+        board.validate()?;
+
+        Ok(board)
+    }
+}
+
+/// bytes in [`Board::to_packed`]
+pub const PACKED_SIZE: usize = 10;
+
+impl Board {
+    /// GNUBG's 80-bit position key with White on roll: for Black and then
+    /// White, every point from their own 1 to 24 and the bar, a 1 bit per
+    /// checker followed by a 0, least significant bit first.
+    pub fn to_packed(&self) -> [u8; PACKED_SIZE] {
+        let mut key = [0; PACKED_SIZE];
+        let mut bit = 0;
+
+        for color in [Bw::Black, Bw::White] {
+            for point in 1..=25 {
+                let val = self[BoardCoord::rel(color, point)];
+
+                if val.matches(color) {
+                    for _ in 0..val.to_count() {
+                        key[bit / 8] |= 1 << (bit % 8);
+                        bit += 1;
+                    }
+                }
+
+                bit += 1;
+            }
+        }
+
+        key
+    }
+
+    pub fn from_packed(key: &[u8; PACKED_SIZE]) -> anyhow::Result<Board> {
+        let mut board = Board::empty();
+        let mut bits = (0..PACKED_SIZE * 8).map(|bit| key[bit / 8] & (1 << (bit % 8)) != 0);
+
+        for color in [Bw::Black, Bw::White] {
+            let mut total = 0;
+
+            for point in 1..=25 {
+                let mut count = 0u8;
+
+                loop {
+                    match bits.next() {
+                        Some(true) => count += 1,
+                        Some(false) => break,
+                        None => bail!("packed board runs out of bits"),
+                    }
+                }
+
+                if count == 0 {
+                    continue;
+                }
+
+                let coord = BoardCoord::rel(color, point);
+
+                if !board[coord].is_empty() {
+                    bail!("both players on the same point in packed board");
+                }
+
+                board[coord] = (color, count).into();
+                total += count;
+            }
+
+            if total > 15 {
+                bail!("{} has {} checkers in packed board", color, total);
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+/// The lanes from 0 to 25 for people to read, and [`Board::to_packed`] for
+/// binary formats.
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let lanes: [i8; 26] = self.0.as_array()[..26].try_into().unwrap();
+            lanes.serialize(serializer)
+        } else {
+            self.to_packed().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let lanes = <[i8; 26]>::deserialize(deserializer)?;
+
+            Board::try_from(lanes).map_err(D::Error::custom)
+        } else {
+            let key = <[u8; PACKED_SIZE]>::deserialize(deserializer)?;
+
+            Board::from_packed(&key).map_err(D::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::types::prim::Bw;
+
+    use super::{Board, BoardError, PACKED_SIZE};
+
+    #[test]
+    fn test_packed() {
+        let rng = &mut rand::thread_rng();
+
+        for _ in 0..1000 {
+            let board: Board = rng.gen();
+            assert_eq!(Board::from_packed(&board.to_packed()).unwrap(), board);
+        }
+
+        // the starting position is the same for both sides
+        let packed = Board::default().to_packed();
+        assert_eq!(packed, Board::default().inverse().to_packed());
+        assert_eq!(
+            packed,
+            [0xe0, 0x73, 0xf0, 0x01, 0x30, 0xe0, 0x73, 0xf0, 0x01, 0x30]
+        );
+
+        assert!(Board::from_packed(&[0xff; PACKED_SIZE]).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let rng = &mut rand::thread_rng();
+
+        for _ in 0..1000 {
+            assert_eq!(rng.gen::<Board>().validate(), Ok(()));
+        }
+
+        let mut lanes = [0i8; 26];
+        lanes[6] = 15;
+        lanes[19] = -15;
+        assert_eq!(Board::try_from(lanes), Ok(Board(lanes_simd(lanes))));
+
+        lanes[25] = 1;
+        assert_eq!(
+            Board::try_from(lanes),
+            Err(BoardError::TooManyCheckers(Bw::White, 16))
+        );
+
+        lanes[25] = -1;
+        assert_eq!(Board::try_from(lanes), Err(BoardError::WrongBar(Bw::White)));
+
+        lanes[25] = 0;
+        lanes[0] = 1;
+        lanes[6] = 14;
+        assert_eq!(Board::try_from(lanes), Err(BoardError::WrongBar(Bw::Black)));
+
+        assert_eq!(Board::try_from([0; 26]), Err(BoardError::BothBorneOff));
+
+        let mut board = Board::default();
+        board.0[30] = 1;
+        assert_eq!(board.validate(), Err(BoardError::Padding(30)));
+
+        assert!(serde_json::from_str::<Board>(&format!("{:?}", [3i8; 26])).is_err());
+    }
+
+    fn lanes_simd(lanes: [i8; 26]) -> std::simd::Simd<i8, 32> {
+        let mut arr = [0; 32];
+        arr[..26].copy_from_slice(&lanes);
+        arr.into()
+    }
+
+    #[test]
+    fn test_serde() {
+        let rng = &mut rand::thread_rng();
+        let board: Board = rng.gen();
+
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+        assert_eq!(
+            serde_json::to_string(&Board::default()).unwrap(),
+            "[0,-2,0,0,0,0,5,0,3,0,0,0,-5,5,0,0,0,-3,0,-5,0,0,0,0,2,0]"
+        );
+    }
+}
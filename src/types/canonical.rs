@@ -0,0 +1,128 @@
+//! Positions from the side to move.
+//!
+//! A [`CanonicalBoard`] always has the player on roll as White, moving from 24
+//! down to 1 with their bar at 25, so a position and its mirror image with the
+//! other side on roll are the same value. After a move it's the opponent's
+//! turn, so every board the generators give back is flipped over again.
+
+use serde::{Deserialize, Serialize};
+
+use super::{board::Board, dice::Dice, play::Play, prim::Bw};
+use crate::movegen::{simd::Simd1MoveGenerator, MoveGen};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CanonicalBoard(Board);
+
+impl CanonicalBoard {
+    /// `board` as `player`, who's on roll, sees it
+    pub fn new(board: &Board, player: Bw) -> Self {
+        match player {
+            Bw::White => Self(board.clone()),
+            Bw::Black => Self(board.inverse()),
+        }
+    }
+
+    /// The board with the side to move as White. Anything that takes a
+    /// `Board` and a `Bw` can be given this and `Bw::White`.
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.0
+    }
+
+    pub fn into_board(self) -> Board {
+        self.0
+    }
+
+    /// back to the real colours, with `player` on roll
+    pub fn to_board(&self, player: Bw) -> Board {
+        match player {
+            Bw::White => self.0.clone(),
+            Bw::Black => self.0.inverse(),
+        }
+    }
+
+    /// the same position, with the other side to move
+    pub fn flip(&self) -> Self {
+        Self(self.0.inverse())
+    }
+
+    /// The positions a legal play of `dice` can lead to, each from the side of
+    /// the opponent, who moves next.
+    pub fn moves(&self, dice: Dice) -> Vec<CanonicalBoard> {
+        self.moves_with::<Simd1MoveGenerator>(dice)
+    }
+
+    pub fn moves_with<Generator: MoveGen>(&self, dice: Dice) -> Vec<CanonicalBoard> {
+        Generator::gen_unique_moves(&self.0, dice, Bw::White)
+            .into_iter()
+            .map(|board| Self(board.inverse()))
+            .collect()
+    }
+
+    /// Like [`moves`](Self::moves), with the play that leads to each one. The
+    /// plays are White's, in this board's coordinates.
+    pub fn plays(&self, dice: Dice) -> Vec<(Play, CanonicalBoard)> {
+        self.plays_with::<Simd1MoveGenerator>(dice)
+    }
+
+    pub fn plays_with<Generator: MoveGen>(&self, dice: Dice) -> Vec<(Play, CanonicalBoard)> {
+        Generator::gen_unique_plays(&self.0, dice, Bw::White)
+            .into_iter()
+            .map(|(play, board)| (play, Self(board.inverse())))
+            .collect()
+    }
+}
+
+impl Board {
+    /// the position as `player`, who's on roll, sees it
+    pub fn canonical(&self, player: Bw) -> CanonicalBoard {
+        CanonicalBoard::new(self, player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::{
+        movegen::{basic::BasicMoveGenerator, MoveGen},
+        types::{board::Board, dice::Dice, prim::Bw},
+    };
+
+    use super::CanonicalBoard;
+
+    #[test]
+    fn test_canonical() {
+        let rng = &mut rand::thread_rng();
+
+        for _ in 0..200 {
+            let board: Board = rng.gen();
+            let player: Bw = rng.gen();
+            let dice: Dice = rng.gen();
+
+            let canonical = board.canonical(player);
+            assert_eq!(canonical, board.inverse().canonical(-player));
+            assert_eq!(canonical.to_board(player), board);
+            assert_eq!(canonical.flip(), board.canonical(-player));
+
+            let mut moves = canonical.moves(dice);
+            let mut expected = BasicMoveGenerator::gen_unique_moves(&board, dice, player)
+                .iter()
+                .map(|after| after.canonical(-player))
+                .collect::<Vec<_>>();
+
+            moves.sort();
+            expected.sort();
+            assert_eq!(moves, expected);
+
+            for (play, after) in canonical.plays(dice) {
+                assert_eq!(play.player, Bw::White);
+
+                let mut board = canonical.board().clone();
+                board.apply(&play);
+                assert_eq!(CanonicalBoard::new(&board, Bw::Black), after);
+            }
+        }
+    }
+}
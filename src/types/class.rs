@@ -0,0 +1,236 @@
+//! What kind of position a board is: GNUBG's classes, and inside contact the
+//! structures players talk about.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    board::{Board, BoardCoord},
+    prim::Bw,
+};
+use crate::{bearoff::home_counts, race::is_contact};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PositionClass {
+    /// somebody has borne everything off
+    Over,
+    /// no contact, and both sides have everything home
+    Bearoff,
+    /// no contact
+    Race,
+    /// The side has at most 6 checkers in play, not counting the ones piled
+    /// on their 1 and 2 points, like GNUBG counts it.
+    Crashed(Bw),
+    /// both sides have a prime of 4 or more points with a checker of the
+    /// other stuck behind it
+    PrimeVsPrime,
+    /// the side holds two or more points in the opponent's home board and
+    /// trails in the race
+    Backgame(Bw),
+    /// the side holds one point in the opponent's home board or on their bar
+    /// point, and trails in the race
+    HoldingGame(Bw),
+    Contact,
+}
+
+/// `color`'s checkers on each point from their side, the bar at 25
+fn side(board: &Board, color: Bw) -> [u8; 26] {
+    let mut counts = [0; 26];
+
+    for (point, count) in counts.iter_mut().enumerate().skip(1) {
+        let v = board[BoardCoord::rel(color, point as u8)];
+        if v.matches(color) {
+            *count = v.to_count();
+        }
+    }
+
+    counts
+}
+
+fn crashed(counts: &[u8; 26]) -> bool {
+    const N: i32 = 6;
+
+    let total: i32 = counts.iter().map(|n| *n as i32).sum();
+    let (ace, two) = (counts[1] as i32, counts[2] as i32);
+
+    if ace > 1 {
+        total <= N + ace || (two > 1 && 1 + total - (ace + two) <= N)
+    } else {
+        total <= N + (two - 1).max(0)
+    }
+}
+
+/// the longest run of made points, from `color`'s side, if it's a prime
+fn prime(counts: &[u8; 26]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut start = None;
+
+    // the bar at 25 ends the last run
+    for (point, n) in counts.iter().enumerate().skip(1) {
+        match (point < 25 && *n >= 2, start) {
+            (true, None) => start = Some(point),
+            (false, Some(from)) => {
+                if best.map_or(0, |(a, b)| b - a + 1) < point - from {
+                    best = Some((from, point - 1));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    best.filter(|(from, to)| to - from >= 3)
+}
+
+/// `color` has a prime and some of the opponent's checkers still behind it
+fn traps(board: &Board, color: Bw) -> bool {
+    let Some((from, _)) = prime(&side(board, color)) else {
+        return false;
+    };
+
+    // below the prime from `color`'s side, their 0 being the opponent's bar
+    (0..from as u8).any(|point| board[BoardCoord::rel(color, point)].matches(-color))
+}
+
+/// made points of `color`'s among their `points`
+fn anchors(counts: &[u8; 26], points: std::ops::RangeInclusive<usize>) -> usize {
+    counts[points].iter().filter(|n| **n >= 2).count()
+}
+
+impl Board {
+    /// The class of the position with `player` on roll. The one-sided ones,
+    /// like a backgame, are `player`'s own if both sides qualify.
+    pub fn classify(&self, player: Bw) -> PositionClass {
+        if self.winner().is_some() {
+            return PositionClass::Over;
+        }
+
+        if !is_contact(self) {
+            let home = |color| home_counts(self, color).is_some();
+
+            return if home(player) && home(-player) {
+                PositionClass::Bearoff
+            } else {
+                PositionClass::Race
+            };
+        }
+
+        let sides = [player, -player];
+
+        if let Some(color) = sides.into_iter().find(|c| crashed(&side(self, *c))) {
+            return PositionClass::Crashed(color);
+        }
+
+        if traps(self, player) && traps(self, -player) {
+            return PositionClass::PrimeVsPrime;
+        }
+
+        let trailing = |color: Bw| self.pip_count(color) > self.pip_count(-color);
+
+        for color in sides {
+            if trailing(color) && anchors(&side(self, color), 19..=24) >= 2 {
+                return PositionClass::Backgame(color);
+            }
+        }
+
+        for color in sides {
+            if trailing(color) && anchors(&side(self, color), 18..=24) == 1 {
+                return PositionClass::HoldingGame(color);
+            }
+        }
+
+        PositionClass::Contact
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::{
+        board::{Board, BoardCoord},
+        prim::{BOrW, Bw},
+    };
+
+    use super::PositionClass;
+
+    fn board(checkers: &[(u8, BOrW)]) -> Board {
+        checkers
+            .iter()
+            .map(|(point, v)| (BoardCoord(*point), *v))
+            .collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        let cases = [
+            (board(&[(20, BOrW::black(2))]), PositionClass::Over),
+            (
+                board(&[(3, BOrW::white(2)), (22, BOrW::black(2))]),
+                PositionClass::Bearoff,
+            ),
+            (
+                board(&[(5, BOrW::white(2)), (10, BOrW::black(2))]),
+                PositionClass::Race,
+            ),
+            (Board::default(), PositionClass::Contact),
+            (
+                board(&[
+                    (24, BOrW::white(3)),
+                    (1, BOrW::white(2)),
+                    (20, BOrW::black(15)),
+                ]),
+                PositionClass::Crashed(Bw::White),
+            ),
+            (
+                board(&[
+                    (24, BOrW::white(2)),
+                    (22, BOrW::white(2)),
+                    (6, BOrW::white(11)),
+                    (20, BOrW::black(5)),
+                    (19, BOrW::black(5)),
+                    (18, BOrW::black(5)),
+                ]),
+                PositionClass::Backgame(Bw::White),
+            ),
+            (
+                board(&[
+                    (20, BOrW::white(2)),
+                    (6, BOrW::white(13)),
+                    (19, BOrW::black(15)),
+                ]),
+                PositionClass::HoldingGame(Bw::White),
+            ),
+            (
+                board(&[
+                    (4, BOrW::white(2)),
+                    (5, BOrW::white(2)),
+                    (6, BOrW::white(2)),
+                    (7, BOrW::white(2)),
+                    (8, BOrW::white(5)),
+                    (23, BOrW::white(2)),
+                    (18, BOrW::black(2)),
+                    (19, BOrW::black(2)),
+                    (20, BOrW::black(2)),
+                    (21, BOrW::black(2)),
+                    (17, BOrW::black(5)),
+                    (2, BOrW::black(2)),
+                ]),
+                PositionClass::PrimeVsPrime,
+            ),
+        ];
+
+        for (board, class) in cases {
+            for player in [Bw::White, Bw::Black] {
+                assert_eq!(board.classify(player), class, "{:?}", board);
+                assert_eq!(board.inverse().classify(player), mirrored(class));
+            }
+        }
+    }
+
+    fn mirrored(class: PositionClass) -> PositionClass {
+        match class {
+            PositionClass::Crashed(color) => PositionClass::Crashed(-color),
+            PositionClass::Backgame(color) => PositionClass::Backgame(-color),
+            PositionClass::HoldingGame(color) => PositionClass::HoldingGame(-color),
+            class => class,
+        }
+    }
+}
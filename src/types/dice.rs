@@ -1,16 +1,63 @@
 use rand::{distributions::Standard, prelude::Distribution, Rng};
 use serde::{Deserialize, Serialize};
 
+use crate::error::BgError;
+
+/// One die, from 1 to 6. Outside the crate it's made with `Die::new` or
+/// `Die::try_from`, so it can't be anything else:
+///
+/// ```compile_fail
+/// let die = backgammon_simd::Die(7);
+/// ```
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize, Ord, PartialOrd)]
 #[repr(transparent)]
-pub struct Die(pub u8);
+pub struct Die(pub(crate) u8);
+
+impl Die {
+    /// `None` unless `n` is from 1 to 6
+    #[inline]
+    pub fn new(n: u8) -> Option<Die> {
+        (1..=6).contains(&n).then_some(Die(n))
+    }
+
+    #[inline]
+    pub fn get(self) -> u8 {
+        self.0
+    }
+
+    /// 1 to 6
+    #[inline]
+    fn all() -> impl Iterator<Item = Die> {
+        (1..=6).filter_map(Die::new)
+    }
+}
+
+impl TryFrom<u8> for Die {
+    type Error = BgError;
+
+    fn try_from(n: u8) -> Result<Self, Self::Error> {
+        Die::new(n).ok_or(BgError::InvalidDie(n))
+    }
+}
 
+// a `Die` on its own can be 0, in `Step`s that aren't there, so it's checked
+// here, where it has to be rolled
 #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "(Die, Die)")]
 pub struct Dice(pub Die, pub Die);
 
 impl Dice {
     pub fn iter_all_possible() -> impl Iterator<Item = Dice> {
-        (1..=6).flat_map(|a| (1..=6).map(move |b| Dice(Die(a), Die(b))))
+        Die::all().flat_map(|a| Die::all().map(move |b| Dice(a, b)))
+    }
+
+    /// the 21 different rolls, higher die first, with how many of the 36 give them
+    pub fn iter_unique() -> impl Iterator<Item = (Dice, u8)> {
+        Die::all().flat_map(|a| {
+            Die::all()
+                .take_while(move |&b| b <= a)
+                .map(move |b| (Dice(a, b), if a == b { 1 } else { 2 }))
+        })
     }
 
     #[must_use]
@@ -33,31 +80,42 @@ impl Dice {
         self.0 .0 << 3 | self.1 .0
     }
 
+    /// panics on anything `as_u8` doesn't give
     #[inline]
     pub fn from_u8(u: u8) -> Dice {
-        Dice(Die(u >> 3), Die(u & 0b111))
+        Dice::try_from((Die(u >> 3), Die(u & 0b111))).expect("Invalid dice byte")
     }
 
+    /// both dice are from 1 to 6, which the public fields don't promise
+    #[inline]
+    pub fn validate(&self) -> Result<(), BgError> {
+        for die in [self.0, self.1] {
+            Die::try_from(die.0)?;
+        }
+
+        Ok(())
+    }
+
+    /// panics on dice outside 1 to 6, see `try_as_alnum`
     #[inline]
     pub fn as_alnum(&self) -> char {
+        self.try_as_alnum().expect("Invalid dice rolls")
+    }
+
+    pub fn try_as_alnum(&self) -> Result<char, BgError> {
+        self.validate()?;
+
         let index = (self.0 .0 - 1) * 6 + (self.1 .0 - 1);
-        match index {
-            0..=9 => ('0' as u8 + index) as char,
-            10..=35 => ('A' as u8 + index - 10) as char,
-            _ => panic!("Invalid dice rolls"),
-        }
+        Ok(match index {
+            0..=9 => (b'0' + index) as char,
+            _ => (b'A' + index - 10) as char,
+        })
     }
 
+    /// panics on anything `as_alnum` doesn't give, see `Dice::try_from`
     #[inline]
     pub fn from_alnum(c: char) -> Self {
-        let index = match c {
-            '0'..='9' => c as u8 - '0' as u8,
-            'A'..='Z' => 10 + (c as u8 - 'A' as u8),
-            _ => panic!("Invalid character"),
-        };
-        let first = index / 6 + 1;
-        let second = index % 6 + 1;
-        Dice(Die(first), Die(second))
+        Self::try_from(c).expect("Invalid character")
     }
 
     #[inline]
@@ -66,6 +124,31 @@ impl Dice {
     }
 }
 
+impl TryFrom<(Die, Die)> for Dice {
+    type Error = BgError;
+
+    fn try_from((a, b): (Die, Die)) -> Result<Self, Self::Error> {
+        let dice = Dice(a, b);
+        dice.validate()?;
+
+        Ok(dice)
+    }
+}
+
+impl TryFrom<char> for Dice {
+    type Error = BgError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        let index = match c {
+            '0'..='9' => c as u8 - b'0',
+            'A'..='Z' => 10 + (c as u8 - b'A'),
+            _ => return Err(BgError::InvalidDice(c)),
+        };
+        Dice::try_from((Die(index / 6 + 1), Die(index % 6 + 1)))
+            .map_err(|_| BgError::InvalidDice(c))
+    }
+}
+
 impl Distribution<Die> for Standard {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Die {
         Die(rng.gen_range(1..=6))
@@ -77,3 +160,59 @@ impl Distribution<Dice> for Standard {
         Dice(rng.gen(), rng.gen())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::BgError,
+        types::{play::Play, prim::Bw},
+    };
+
+    use super::{Dice, Die};
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(Die::new(0), None);
+        assert_eq!(Die::new(7), None);
+        assert_eq!(Die::new(3), Some(Die(3)));
+        assert_eq!(Die::try_from(7), Err(BgError::InvalidDie(7)));
+        assert_eq!(Die::try_from(4).map(Die::get), Ok(4));
+
+        assert_eq!(Dice::iter_all_possible().count(), 36);
+        assert_eq!(
+            Dice::iter_unique().map(|(_, n)| n as usize).sum::<usize>(),
+            36
+        );
+        for dice in Dice::iter_all_possible() {
+            assert_eq!(Dice::from_u8(dice.as_u8()), dice);
+        }
+
+        for dice in Dice::iter_all_possible() {
+            assert_eq!(Dice::try_from(dice.as_alnum()), Ok(dice));
+        }
+
+        assert_eq!(Dice::try_from('Z'), Ok(Dice(Die(6), Die(6))));
+        assert_eq!(Dice::try_from('a'), Err(BgError::InvalidDice('a')));
+
+        assert_eq!(
+            Dice(Die(7), Die(1)).try_as_alnum(),
+            Err(BgError::InvalidDie(7))
+        );
+        assert_eq!(
+            Dice(Die(2), Die(0)).try_as_alnum(),
+            Err(BgError::InvalidDie(0))
+        );
+        assert_eq!(Dice(Die(6), Die(6)).try_as_alnum(), Ok('Z'));
+
+        assert!(serde_json::from_str::<Dice>("[9,1]").is_err());
+        assert_eq!(
+            serde_json::from_str::<Dice>("[6,1]").unwrap(),
+            Dice(Die(6), Die(1))
+        );
+
+        // unused steps have a 0 die
+        let play = Play::new(Bw::White);
+        let json = serde_json::to_string(&play).unwrap();
+        assert_eq!(serde_json::from_str::<Play>(&json).unwrap(), play);
+    }
+}
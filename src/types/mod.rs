@@ -1,4 +1,7 @@
 pub mod board;
+pub mod canonical;
+pub mod class;
 pub mod dice;
+pub mod play;
 
 pub mod prim;
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    board::{Board, BoardCoord},
+    dice::Die,
+    prim::Bw,
+};
+
+#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+/// a single checker moved by a single die.
+/// `to` is `BoardCoord::off(player)` when the checker is borne off
+pub struct Step {
+    pub from: BoardCoord,
+    pub to: BoardCoord,
+    pub die: Die,
+    pub hit: bool,
+}
+
+impl Step {
+    const NONE: Step = Step {
+        from: BoardCoord(0),
+        to: BoardCoord(0),
+        die: Die(0),
+        hit: false,
+    };
+
+    /// the step `player` made with `die` to get from `before` to `after`
+    pub(crate) fn between(before: &Board, after: &Board, player: Bw, die: Die) -> Step {
+        let diff = after.0 - before.0;
+        let sign = match player {
+            Bw::White => 1,
+            Bw::Black => -1,
+        };
+
+        // the opponent's bar is at our "off" lane and only changes on a hit
+        let off = BoardCoord::off(player);
+
+        let mut from = None;
+        let mut to = off;
+
+        for lane in 0..=25 {
+            let coord = BoardCoord(lane);
+            if coord == off {
+                continue;
+            }
+
+            let d = diff[lane as usize] * sign;
+
+            if d < 0 {
+                from = Some(coord);
+            } else if d > 0 {
+                to = coord;
+            }
+        }
+
+        Step {
+            from: from.expect("no checker moved between the boards"),
+            to,
+            die,
+            hit: diff[off.0 as usize] != 0,
+        }
+    }
+}
+
+#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+/// up to four steps of one player, in the order they're made
+pub struct Play {
+    pub player: Bw,
+    len: u8,
+    steps: [Step; 4],
+}
+
+impl Play {
+    #[inline]
+    pub fn new(player: Bw) -> Self {
+        Self {
+            player,
+            len: 0,
+            steps: [Step::NONE; 4],
+        }
+    }
+
+    #[inline]
+    pub fn steps(&self) -> &[Step] {
+        &self.steps[..self.len as usize]
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn push(&mut self, step: Step) {
+        assert!(self.len < 4, "a play has at most four steps");
+
+        self.steps[self.len as usize] = step;
+        self.len += 1;
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn with(&self, step: Step) -> Play {
+        let mut play = *self;
+        play.push(step);
+        play
+    }
+
+    #[inline]
+    pub fn dice(&self) -> impl Iterator<Item = Die> + '_ {
+        self.steps().iter().map(|s| s.die)
+    }
+
+    #[inline]
+    pub fn hits(&self) -> usize {
+        self.steps().iter().filter(|s| s.hit).count()
+    }
+}
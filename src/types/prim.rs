@@ -49,6 +49,32 @@ impl Neg for Bw {
     }
 }
 
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug, Serialize, Deserialize)]
+/// how a game was won; ordered, so `kind >= WinKind::Gammon` includes backgammons
+pub enum WinKind {
+    Single = 1,
+    Gammon = 2,
+    Backgammon = 3,
+}
+
+impl WinKind {
+    /// points at a 1-cube
+    #[inline]
+    pub fn points(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl Display for WinKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WinKind::Single => write!(f, "single game"),
+            WinKind::Gammon => write!(f, "gammon"),
+            WinKind::Backgammon => write!(f, "backgammon"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct BOrW(pub i8);
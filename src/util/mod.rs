@@ -1 +1,2 @@
+pub(crate) mod table;
 pub mod timings;
@@ -0,0 +1,101 @@
+//! The layout the bearoff databases and the hypergammon solution share: eight
+//! bytes of magic, some little-endian u32 parameters, then fixed-size records
+//! with no pointers or padding, so a file can be memory-mapped as it is.
+
+use anyhow::{ensure, Context};
+
+/// A file type with `N` parameters in its header.
+pub(crate) struct TableFormat<const N: usize> {
+    pub magic: &'static [u8; 8],
+    /// what the file is, for errors
+    pub name: &'static str,
+}
+
+impl<const N: usize> TableFormat<N> {
+    pub const fn header_len(&self) -> usize {
+        8 + 4 * N
+    }
+
+    /// the header, with room for `records` bytes after it
+    pub fn start(&self, params: [u32; N], records: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.header_len() + records);
+        data.extend_from_slice(self.magic);
+
+        for param in params {
+            data.extend_from_slice(&param.to_le_bytes());
+        }
+
+        data
+    }
+
+    /// Checks the magic, then that `bytes` are as long as `records` says the
+    /// records are for the parameters, and gives back the parameters.
+    /// `records` errors on parameters it doesn't support, or a size that
+    /// doesn't fit in memory.
+    pub fn read(
+        &self,
+        bytes: &[u8],
+        records: impl FnOnce([u32; N]) -> anyhow::Result<usize>,
+    ) -> anyhow::Result<[u32; N]> {
+        ensure!(
+            bytes.len() >= self.header_len() && &bytes[..8] == self.magic,
+            "not a {}",
+            self.name
+        );
+
+        let params = std::array::from_fn(|i| {
+            let at = 8 + 4 * i;
+            u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+        });
+
+        let expected = records(params)?
+            .checked_add(self.header_len())
+            .with_context(|| format!("{} is too big", self.name))?;
+
+        ensure!(
+            bytes.len() == expected,
+            "{} is {} bytes, should be {}",
+            self.name,
+            bytes.len(),
+            expected
+        );
+
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::{ensure, Context};
+
+    use super::TableFormat;
+
+    const FORMAT: TableFormat<2> = TableFormat {
+        magic: b"TESTING\0",
+        name: "test table",
+    };
+
+    fn records([a, b]: [u32; 2]) -> anyhow::Result<usize> {
+        ensure!(a < 100, "{a} is too many");
+        (a as usize).checked_mul(b as usize).context("too big")
+    }
+
+    #[test]
+    fn test_read() {
+        let mut data = FORMAT.start([3, 2], 6);
+        assert_eq!(data.len(), FORMAT.header_len());
+
+        data.extend_from_slice(&[0; 6]);
+        assert_eq!(FORMAT.read(&data, records).unwrap(), [3, 2]);
+
+        assert!(FORMAT.read(&data[..data.len() - 1], records).is_err());
+        assert!(FORMAT.read(&data[..10], records).is_err());
+        assert!(FORMAT.read(&FORMAT.start([300, 2], 0), records).is_err());
+        assert!(FORMAT
+            .read(&FORMAT.start([1, u32::MAX], 0), records)
+            .is_err());
+
+        data[0] = b'X';
+        assert!(FORMAT.read(&data, records).is_err());
+    }
+}
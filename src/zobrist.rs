@@ -0,0 +1,345 @@
+//! Zobrist hashing: a 64-bit key for every board, the XOR of one random
+//! number per lane and checker count.
+//!
+//! The numbers come from a fixed seed, so keys are the same from run to run
+//! and machine to machine and can go into datasets. Changing a lane changes
+//! the key by two XORs, which [`HashedBoard`] does as checkers move.
+
+use std::{
+    hash::{Hash, Hasher},
+    sync::Mutex,
+};
+
+use crate::{
+    eval::Probabilities,
+    types::{
+        board::{Board, BoardCoord},
+        play::{Play, Step},
+        prim::Bw,
+    },
+};
+
+const LANES: usize = 26;
+
+/// from -15 to 15 checkers
+const COUNTS: usize = 31;
+
+const fn splitmix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    (state, z ^ (z >> 31))
+}
+
+const fn keys() -> [[u64; COUNTS]; LANES] {
+    let mut keys = [[0; COUNTS]; LANES];
+    let mut state = 0x6261_636b_6761_6d6d;
+
+    let mut lane = 0;
+    while lane < LANES {
+        let mut count = 0;
+        while count < COUNTS {
+            // an empty lane is worth nothing, so it needs no updating
+            if count != COUNTS / 2 {
+                let (next, key) = splitmix(state);
+                state = next;
+                keys[lane][count] = key;
+            }
+            count += 1;
+        }
+        lane += 1;
+    }
+
+    keys
+}
+
+static KEYS: [[u64; COUNTS]; LANES] = keys();
+
+/// mixed into the key of a position with Black on roll
+pub const BLACK_ON_ROLL: u64 = splitmix(0x6f6e_2072_6f6c_6c21).1;
+
+#[inline]
+fn key(lane: usize, value: i8) -> u64 {
+    KEYS[lane][(value as isize + COUNTS as isize / 2) as usize]
+}
+
+impl Board {
+    /// The Zobrist key of the checkers, whoever is on roll.
+    pub fn zobrist(&self) -> u64 {
+        self.0.as_array()[..LANES]
+            .iter()
+            .enumerate()
+            .fold(0, |hash, (lane, value)| hash ^ key(lane, *value))
+    }
+
+    /// the key of the position with `player` on roll
+    pub fn zobrist_on_roll(&self, player: Bw) -> u64 {
+        match player {
+            Bw::White => self.zobrist(),
+            Bw::Black => self.zobrist() ^ BLACK_ON_ROLL,
+        }
+    }
+}
+
+/// Equal boards have equal keys, so hashing the key is enough, and cheaper
+/// than hashing the whole vector.
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.zobrist());
+    }
+}
+
+/// A board that keeps its Zobrist key up to date as it changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashedBoard {
+    board: Board,
+    zobrist: u64,
+}
+
+impl HashedBoard {
+    pub fn new(board: Board) -> Self {
+        Self {
+            zobrist: board.zobrist(),
+            board,
+        }
+    }
+
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    #[inline]
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    pub fn into_board(self) -> Board {
+        self.board
+    }
+
+    /// runs `change` on the board, which may only touch `lanes`
+    #[inline]
+    fn update(&mut self, lanes: [BoardCoord; 3], change: impl FnOnce(&mut Board)) {
+        let before = lanes.map(|coord| self.board[coord].0);
+        change(&mut self.board);
+
+        for (i, coord) in lanes.iter().enumerate() {
+            // the same lane twice would cancel out
+            if lanes[..i].contains(coord) {
+                continue;
+            }
+
+            let lane = coord.0 as usize;
+            self.zobrist ^= key(lane, before[i]) ^ key(lane, self.board.0[lane]);
+        }
+    }
+
+    /// the lanes a step can change: where it starts and ends, and the
+    /// opponent's bar in case of a hit
+    fn step_lanes(player: Bw, step: &Step) -> [BoardCoord; 3] {
+        [step.from, step.to, BoardCoord::bar(-player)]
+    }
+
+    #[inline]
+    pub fn apply_step(&mut self, player: Bw, step: &Step) {
+        self.update(Self::step_lanes(player, step), |board| {
+            board.apply_step(player, step)
+        });
+    }
+
+    #[inline]
+    pub fn unapply_step(&mut self, player: Bw, step: &Step) {
+        self.update(Self::step_lanes(player, step), |board| {
+            board.unapply_step(player, step)
+        });
+    }
+
+    pub fn apply(&mut self, play: &Play) {
+        for step in play.steps() {
+            self.apply_step(play.player, step);
+        }
+    }
+
+    pub fn unapply(&mut self, play: &Play) {
+        for step in play.steps().iter().rev() {
+            self.unapply_step(play.player, step);
+        }
+    }
+
+    #[inline]
+    pub fn inc_bar(&mut self, color: Bw) {
+        let bar = BoardCoord::bar(color);
+        self.update([bar; 3], |board| board.inc_bar(color));
+    }
+
+    #[inline]
+    pub fn dec_bar(&mut self, color: Bw) {
+        let bar = BoardCoord::bar(color);
+        self.update([bar; 3], |board| board.dec_bar(color));
+    }
+}
+
+impl From<Board> for HashedBoard {
+    fn from(board: Board) -> Self {
+        Self::new(board)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    key: u64,
+    depth: u8,
+    probabilities: Probabilities,
+}
+
+/// A fixed number of evaluations, each under its own lock, found by key.
+///
+/// A slot holds one position: a new one replaces it unless the slot already
+/// has the same position searched deeper.
+pub struct TranspositionTable {
+    entries: Box<[Mutex<Option<Entry>>]>,
+}
+
+impl TranspositionTable {
+    /// room for `capacity` positions, rounded up to a power of two
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: (0..capacity.max(1).next_power_of_two())
+                .map(|_| Mutex::new(None))
+                .collect(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    fn slot(&self, key: u64) -> &Mutex<Option<Entry>> {
+        &self.entries[key as usize & (self.entries.len() - 1)]
+    }
+
+    /// what's stored for `key`, if it was searched at least `depth` deep
+    pub fn get(&self, key: u64, depth: u8) -> Option<Probabilities> {
+        let entry = (*self.slot(key).lock().unwrap())?;
+
+        (entry.key == key && entry.depth >= depth).then_some(entry.probabilities)
+    }
+
+    pub fn insert(&self, key: u64, depth: u8, probabilities: Probabilities) {
+        let mut slot = self.slot(key).lock().unwrap();
+
+        if let Some(entry) = *slot {
+            if entry.key == key && entry.depth > depth {
+                return;
+            }
+        }
+
+        *slot = Some(Entry {
+            key,
+            depth,
+            probabilities,
+        });
+    }
+
+    pub fn clear(&self) {
+        for entry in self.entries.iter() {
+            *entry.lock().unwrap() = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, thread};
+
+    use rand::{seq::SliceRandom, Rng};
+
+    use crate::{
+        eval::Probabilities,
+        movegen::{simd::Simd1MoveGenerator, MoveGen},
+        types::{board::Board, prim::Bw},
+    };
+
+    use super::{HashedBoard, TranspositionTable};
+
+    #[test]
+    fn test_incremental() {
+        let rng = &mut rand::thread_rng();
+
+        for _ in 0..100 {
+            let board: Board = rng.gen();
+            let player = rng.gen();
+
+            let plays = Simd1MoveGenerator::gen_unique_plays(&board, rng.gen(), player);
+            let Some((play, after)) = plays.choose(rng) else {
+                continue;
+            };
+
+            let mut hashed = HashedBoard::new(board.clone());
+            hashed.apply(play);
+            assert_eq!(hashed.board(), after);
+            assert_eq!(hashed.zobrist(), after.zobrist());
+
+            hashed.inc_bar(Bw::Black);
+            hashed.inc_bar(Bw::White);
+            hashed.dec_bar(Bw::Black);
+            assert_eq!(hashed.zobrist(), hashed.board().zobrist());
+
+            hashed.dec_bar(Bw::White);
+            hashed.unapply(play);
+            assert_eq!(hashed.zobrist(), board.zobrist());
+            assert_eq!(hashed.into_board(), board);
+        }
+    }
+
+    #[test]
+    fn test_keys() {
+        let board = Board::default();
+
+        assert_ne!(board.zobrist(), Board::empty().zobrist());
+        assert_eq!(Board::empty().zobrist(), 0);
+        assert_ne!(
+            board.zobrist_on_roll(Bw::White),
+            board.zobrist_on_roll(Bw::Black)
+        );
+        // keys are part of datasets, they mustn't change
+        assert_eq!(board.zobrist(), 0x4e62_f0be_de2f_d7db);
+    }
+
+    #[test]
+    fn test_table() {
+        let table = Arc::new(TranspositionTable::new(100));
+        assert_eq!(table.capacity(), 128);
+
+        let p = Probabilities {
+            win: 0.5,
+            ..Default::default()
+        };
+
+        let writers = (0..4u64)
+            .map(|i| {
+                let table = table.clone();
+                thread::spawn(move || table.insert(i, 2, p))
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(table.get(3, 2), Some(p));
+        assert_eq!(table.get(3, 3), None);
+        assert_eq!(table.get(3 + 128, 0), None);
+
+        // shallower results don't replace deeper ones of the same position
+        table.insert(3, 1, Probabilities::default());
+        assert_eq!(table.get(3, 0), Some(p));
+
+        table.clear();
+        assert_eq!(table.get(0, 0), None);
+    }
+}